@@ -0,0 +1,276 @@
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    AppError, Config,
+    pixel::{Filter, Size32},
+};
+
+/// Help text listing the command-line flags, which double as config file keys
+pub const USAGE: &str = "\
+Usage: fractal [OPTIONS]
+
+Options (also accepted as `key = value` lines in the config file, with `_` for `-`):
+  --window-size <WxH>         Size of the window in pixels
+  --aliasing-factor <N>       Anti-aliasing multiplier for the render buffer (1-8)
+  --adaptive-samples <N>      Maximum extra samples for edge pixels, 0 to disable (0-64)
+  --downsample-filter <NAME>  Downsampling filter: box, tent, lanczos or mitchell
+  --zoom-factor <X>           Speed multiplier for zooming in/out
+  --target-fps <X>            Target frames per second for the main loop
+  --worker-threads <N>        Number of worker threads for parallel rendering
+  --motion-decay <X>          Decay factor for motion after input (0-1)
+  --max-iter <N>              Maximum number of iterations for escape calculation
+  --auto-iter <BOOL>          Derive the maximum number of iterations from the view
+  --color-cycle <N>           Iteration divisor for color cycling
+  --saturation <X>            Color saturation (0-1)
+  --interior-value <X>        Brightness of interior pixels (0-1)
+  --config <PATH>             Config file to read instead of the default
+  --fresh                     Start from the initial view instead of the last session
+  --replay <FILE>             Play a recording made with the R key in the window
+  --render <FILE>             Save each frame of a recording as a bitmap, without a window
+  -h, --help                  Print this help
+";
+
+/// Builds the configuration from a base, then the config file, then command-line arguments
+///
+/// # Arguments
+/// * `args` - Command-line arguments, without the program name
+/// * `base` - Defaults, possibly with the settings of the last session applied
+///
+/// # Returns
+/// The validated configuration, or a `InvalidConfig` describing the first invalid setting
+pub fn load(args: &[String], base: Config) -> Result<Config, AppError> {
+    let mut config = base;
+    let options = parse_args(args)?;
+
+    // An explicit config file must exist, the default one is optional
+    let explicit = options
+        .iter()
+        .rev()
+        .find(|(key, _)| key == "config")
+        .map(|(_, value)| PathBuf::from(value));
+    if let Some(path) = explicit.clone().or_else(default_path) {
+        match fs::read_to_string(&path) {
+            Ok(text) => apply_file(&mut config, &text, &path)?,
+            Err(e) if e.kind() == ErrorKind::NotFound && explicit.is_none() => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    for (key, value) in options.iter().filter(|(key, _)| key != "config") {
+        set(&mut config, key, value).map_err(|msg| {
+            AppError::InvalidConfig(format!("--{}: {msg}", key.replace('_', "-")))
+        })?;
+    }
+    validate(&config).map_err(AppError::InvalidConfig)?;
+    Ok(config)
+}
+
+/// Location of the config file in the user's config directory
+fn default_path() -> Option<PathBuf> {
+    Some(user_dir("XDG_CONFIG_HOME", ".config")?.join("config"))
+}
+
+/// Directory of the application inside a per-user base directory
+///
+/// # Arguments
+/// * `xdg_var` - Environment variable naming the base directory
+/// * `home_fallback` - Base directory relative to the home directory when the variable is unset
+///
+/// # Returns
+/// The `fractal` directory inside the base directory, or `None` without a home directory
+pub fn user_dir(xdg_var: &str, home_fallback: &str) -> Option<PathBuf> {
+    let dir = env::var_os(xdg_var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(app_data)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))?;
+    Some(dir.join("fractal"))
+}
+
+/// Per-user application data directory on Windows
+#[cfg(windows)]
+fn app_data() -> Option<PathBuf> {
+    env::var_os("APPDATA").map(PathBuf::from)
+}
+
+#[cfg(not(windows))]
+fn app_data() -> Option<PathBuf> {
+    None
+}
+
+/// Removes a `--name value` or `--name=value` flag that is not a setting from the arguments
+///
+/// # Returns
+/// The value of the last occurrence, or `None` when the flag is absent
+pub fn take_flag(args: &mut Vec<String>, name: &str) -> Result<Option<String>, AppError> {
+    let flag = format!("--{name}");
+    let mut value = None;
+    let mut index = 0;
+    while index < args.len() {
+        if let Some(inline) = args[index].strip_prefix(&format!("{flag}=")) {
+            value = Some(inline.to_string());
+            args.remove(index);
+        } else if args[index] == flag {
+            if index + 1 >= args.len() {
+                return Err(AppError::InvalidConfig(format!("{flag}: missing value")));
+            }
+            value = Some(args.remove(index + 1));
+            args.remove(index);
+        } else {
+            index += 1;
+        }
+    }
+    Ok(value)
+}
+
+/// Splits `--key value` and `--key=value` arguments into key/value pairs with snake_case keys
+fn parse_args(args: &[String]) -> Result<Vec<(String, String)>, AppError> {
+    let mut options = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            return Err(AppError::InvalidConfig(format!(
+                "unexpected argument '{arg}'"
+            )));
+        };
+        let (key, value) = match flag.split_once('=') {
+            Some((key, value)) => (key, value.to_string()),
+            None => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| AppError::InvalidConfig(format!("--{flag}: missing value")))?;
+                (flag, value.clone())
+            }
+        };
+        options.push((key.replace('-', "_"), value));
+    }
+    Ok(options)
+}
+
+/// Applies the `key = value` lines of a config file, skipping blank lines and `#` comments
+fn apply_file(config: &mut Config, text: &str, path: &Path) -> Result<(), AppError> {
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let result = match line.split_once('=') {
+            Some((key, value)) => set(config, key.trim(), value.trim()),
+            None => Err("expected `key = value`".to_string()),
+        };
+        result.map_err(|msg| {
+            AppError::InvalidConfig(format!("{}:{}: {msg}", path.display(), number + 1))
+        })?;
+    }
+    Ok(())
+}
+
+/// Sets a single configuration field from its textual value
+pub fn set(config: &mut Config, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "window_size" => config.window_size = parse_size(value)?,
+        "aliasing_factor" => config.aliasing_factor = parse(value)?,
+        "adaptive_samples" => config.adaptive_samples = parse(value)?,
+        "downsample_filter" => {
+            config.downsample_filter =
+                Filter::from_name(value).ok_or_else(|| format!("unknown filter '{value}'"))?
+        }
+        "zoom_factor" => config.zoom_factor = parse(value)?,
+        "target_fps" => config.target_fps = parse(value)?,
+        "worker_threads" => config.worker_threads = parse(value)?,
+        "motion_decay" => config.motion_decay = parse(value)?,
+        "max_iter" => config.max_iter = parse(value)?,
+        "auto_iter" => config.auto_iter = parse(value)?,
+        "color_cycle" => config.color_cycle = parse(value)?,
+        "saturation" => config.saturation = parse(value)?,
+        "interior_value" => config.interior_value = parse(value)?,
+        _ => return Err(format!("unknown setting '{key}'")),
+    }
+    Ok(())
+}
+
+pub fn parse<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{value}'"))
+}
+
+/// Parses a size written as `WIDTHxHEIGHT`
+fn parse_size(value: &str) -> Result<Size32, String> {
+    let (w, h) = value
+        .split_once('x')
+        .ok_or_else(|| format!("invalid size '{value}', expected WIDTHxHEIGHT"))?;
+    Ok(Size32 {
+        w: parse(w)?,
+        h: parse(h)?,
+    })
+}
+
+/// Checks that every setting is within its usable range
+pub fn validate(config: &Config) -> Result<(), String> {
+    // Keeps pixel counts and indices of the supersampled buffer within u32
+    const MAX_WINDOW_SIZE: u32 = 16384;
+    const MAX_BUFFER_PIXELS: u64 = 1 << 28;
+    // Zoom keys scroll by 10 per frame, which must not shrink the view to nothing
+    const MAX_ZOOM_FACTOR: f32 = 0.05;
+    let aliasing_factor = config.aliasing_factor as u64;
+    let buffer_pixels = (config.window_size.w as u64 * config.window_size.h as u64)
+        .saturating_mul(aliasing_factor.saturating_mul(aliasing_factor));
+    let checks = [
+        (
+            config.window_size.w > 0 && config.window_size.h > 0,
+            "window_size must be at least 1x1",
+        ),
+        (
+            config.window_size.w <= MAX_WINDOW_SIZE && config.window_size.h <= MAX_WINDOW_SIZE,
+            "window_size must be at most 16384x16384",
+        ),
+        (
+            (1..=8).contains(&config.aliasing_factor),
+            "aliasing_factor must be between 1 and 8",
+        ),
+        (
+            buffer_pixels <= MAX_BUFFER_PIXELS,
+            "window_size times aliasing_factor must stay within 2^28 buffer pixels",
+        ),
+        (
+            config.adaptive_samples <= 64,
+            "adaptive_samples must be at most 64",
+        ),
+        (
+            config.zoom_factor > 0.0 && config.zoom_factor <= MAX_ZOOM_FACTOR,
+            "zoom_factor must be positive and at most 0.05",
+        ),
+        (
+            config.target_fps > 0.0 && config.target_fps.is_finite(),
+            "target_fps must be positive and finite",
+        ),
+        (
+            config.worker_threads > 0,
+            "worker_threads must be at least 1",
+        ),
+        (
+            (0.0..1.0).contains(&config.motion_decay),
+            "motion_decay must be between 0 and 1",
+        ),
+        (config.max_iter > 0, "max_iter must be at least 1"),
+        (config.color_cycle > 0, "color_cycle must be at least 1"),
+        (
+            (0.0..=1.0).contains(&config.saturation),
+            "saturation must be between 0 and 1",
+        ),
+        (
+            (0.0..=1.0).contains(&config.interior_value),
+            "interior_value must be between 0 and 1",
+        ),
+    ];
+    match checks.into_iter().find(|(valid, _)| !valid) {
+        Some((_, msg)) => Err(msg.to_string()),
+        None => Ok(()),
+    }
+}
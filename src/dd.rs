@@ -0,0 +1,101 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use rug::Float;
+
+/// Unevaluated sum of two f64 values, giving about 106 bits of mantissa
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+impl DoubleDouble {
+    pub const fn new(value: f64) -> Self {
+        Self { hi: value, lo: 0.0 }
+    }
+
+    /// Rounds an arbitrary precision value to the nearest double-double
+    pub fn from_float(value: &Float) -> Self {
+        let hi = value.to_f64();
+        let lo = Float::with_val(value.prec(), value - hi).to_f64();
+        Self { hi, lo }
+    }
+
+    /// Sum of two f64 values, exact as hi + lo
+    fn two_sum(a: f64, b: f64) -> Self {
+        let hi = a + b;
+        let b_virtual = hi - a;
+        let lo = (a - (hi - b_virtual)) + (b - b_virtual);
+        Self { hi, lo }
+    }
+
+    /// Sum of two f64 values where |a| >= |b|, exact as hi + lo
+    fn quick_two_sum(a: f64, b: f64) -> Self {
+        let hi = a + b;
+        let lo = b - (hi - a);
+        Self { hi, lo }
+    }
+
+    /// Product of two f64 values, exact as hi + lo
+    fn two_prod(a: f64, b: f64) -> Self {
+        let hi = a * b;
+        let lo = a.mul_add(b, -hi);
+        Self { hi, lo }
+    }
+
+    pub fn square(self) -> Self {
+        let p = Self::two_prod(self.hi, self.hi);
+        Self::quick_two_sum(p.hi, p.lo + 2.0 * self.hi * self.lo)
+    }
+
+    /// Multiplies by two, which is exact
+    pub fn double(self) -> Self {
+        Self {
+            hi: self.hi * 2.0,
+            lo: self.lo * 2.0,
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        if self.hi < 0.0 { -self } else { self }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let s = Self::two_sum(self.hi, other.hi);
+        let t = Self::two_sum(self.lo, other.lo);
+        let s = Self::quick_two_sum(s.hi, s.lo + t.hi);
+        Self::quick_two_sum(s.hi, s.lo + t.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let p = Self::two_prod(self.hi, other.hi);
+        Self::quick_two_sum(p.hi, p.lo + (self.hi * other.lo + self.lo * other.hi))
+    }
+}
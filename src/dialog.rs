@@ -0,0 +1,203 @@
+use sdl2::{
+    event::Event,
+    keyboard::Keycode,
+    pixels::Color,
+    rect::Rect,
+    render::{BlendMode, WindowCanvas},
+};
+
+use crate::font::{ADVANCE, LINE_HEIGHT, draw_text, text_width};
+
+/// Screen pixels per font pixel
+const SCALE: u32 = 2;
+/// Distance between the dialog edges and its text in screen pixels
+const PADDING: i32 = 12;
+
+/// What the owner of a dialog should do after an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogAction {
+    /// Keep the dialog open
+    Edit,
+    /// Close the dialog, discarding its values
+    Cancel,
+    /// Use the values of the dialog
+    Submit,
+}
+
+/// A single labeled text field
+struct Field {
+    label: &'static str,
+    value: String,
+}
+
+/// An in-window form of text fields, edited with the keyboard
+pub struct Dialog {
+    title: &'static str,
+    fields: Vec<Field>,
+    focus: usize,
+    /// Set when the dialog was opened by a key, whose text input event is still to come
+    skip_text: Option<char>,
+    /// Message shown below the fields, e.g. why the values were rejected
+    pub error: Option<String>,
+}
+
+impl Dialog {
+    /// Creates a dialog with initial field values
+    ///
+    /// # Arguments
+    /// * `title` - Heading of the dialog
+    /// * `fields` - Label and initial value of each field
+    /// * `opened_by` - Character of the key that opened the dialog, not to be typed into it
+    pub fn new(
+        title: &'static str,
+        fields: Vec<(&'static str, String)>,
+        opened_by: Option<char>,
+    ) -> Self {
+        Self {
+            title,
+            fields: fields
+                .into_iter()
+                .map(|(label, value)| Field { label, value })
+                .collect(),
+            focus: 0,
+            skip_text: opened_by,
+            error: None,
+        }
+    }
+
+    /// Current value of each field
+    pub fn values(&self) -> Vec<&str> {
+        self.fields
+            .iter()
+            .map(|field| field.value.as_str())
+            .collect()
+    }
+
+    /// Replaces the value of each field in order, e.g. with a whole pasted form
+    pub fn set_values(&mut self, values: Vec<String>) {
+        self.skip_text = None;
+        self.error = None;
+        for (field, value) in self.fields.iter_mut().zip(values) {
+            field.value = value;
+        }
+    }
+
+    /// Updates the dialog with a keyboard event
+    pub fn handle(&mut self, event: &Event) -> DialogAction {
+        match event {
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => return DialogAction::Cancel,
+            Event::KeyDown {
+                keycode: Some(Keycode::Return | Keycode::KpEnter),
+                ..
+            } => return DialogAction::Submit,
+            Event::KeyDown {
+                keycode: Some(Keycode::Tab | Keycode::Down),
+                ..
+            } => {
+                self.focus = (self.focus + 1) % self.fields.len();
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Up),
+                ..
+            } => {
+                self.focus = (self.focus + self.fields.len() - 1) % self.fields.len();
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Backspace),
+                ..
+            } => {
+                self.fields[self.focus].value.pop();
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Delete),
+                ..
+            } => {
+                self.fields[self.focus].value.clear();
+            }
+            Event::TextInput { text, .. } => {
+                let mut chars = text.chars();
+                if let Some(skip) = self.skip_text.take()
+                    && text.len() == 1
+                    && chars.next().is_some_and(|c| c.eq_ignore_ascii_case(&skip))
+                {
+                    return DialogAction::Edit;
+                }
+                self.insert(text);
+            }
+            _ => {}
+        }
+        DialogAction::Edit
+    }
+
+    /// Types text into the focused field, ignoring control characters
+    pub fn insert(&mut self, text: &str) {
+        self.skip_text = None;
+        let value = &mut self.fields[self.focus].value;
+        value.extend(text.chars().filter(|c| !c.is_control()));
+    }
+
+    /// Draws the dialog over the top of the canvas
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let (width, _) = canvas.output_size()?;
+        let line = (LINE_HEIGHT * SCALE) as i32;
+        let label_width = self
+            .fields
+            .iter()
+            .map(|field| text_width(field.label, SCALE))
+            .max()
+            .unwrap_or(0) as i32
+            + PADDING;
+        let value_left = PADDING * 2 + label_width;
+        let rows = self.fields.len() as i32 + 3;
+        let frame = Rect::new(
+            PADDING,
+            PADDING,
+            width.saturating_sub(2 * PADDING as u32),
+            (rows * line + PADDING) as u32,
+        );
+        // Only show the end of values that don't fit
+        let columns = ((frame.right() - value_left - PADDING) / (ADVANCE * SCALE) as i32).max(1);
+
+        let color = canvas.draw_color();
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 0xC0));
+        canvas.fill_rect(frame)?;
+
+        let left = PADDING * 2;
+        let mut top = PADDING * 2;
+        canvas.set_draw_color(Color::RGB(0xFF, 0xFF, 0xFF));
+        draw_text(canvas, self.title, left, top, SCALE)?;
+        for (index, field) in self.fields.iter().enumerate() {
+            top += line;
+            let skip = field
+                .value
+                .chars()
+                .count()
+                .saturating_sub(columns as usize - 1);
+            let mut value: String = field.value.chars().skip(skip).collect();
+            if index == self.focus {
+                value.push('_');
+                canvas.set_draw_color(Color::RGB(0xFF, 0xD0, 0x40));
+            } else {
+                canvas.set_draw_color(Color::RGB(0xA0, 0xA0, 0xA0));
+            }
+            draw_text(canvas, field.label, left, top, SCALE)?;
+            canvas.set_draw_color(Color::RGB(0xFF, 0xFF, 0xFF));
+            draw_text(canvas, &value, value_left, top, SCALE)?;
+        }
+        top += line;
+        if let Some(error) = &self.error {
+            canvas.set_draw_color(Color::RGB(0xFF, 0x60, 0x60));
+            draw_text(canvas, error, left, top, SCALE)?;
+        }
+        top += line;
+        canvas.set_draw_color(Color::RGB(0xA0, 0xA0, 0xA0));
+        draw_text(canvas, "TAB NEXT   ENTER GO   ESC CANCEL", left, top, SCALE)?;
+
+        canvas.set_draw_color(color);
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::VecDeque;
+
+/// Bounded list of visited states with a cursor for stepping back and forward
+pub struct History<T> {
+    entries: VecDeque<T>,
+    cursor: usize,
+    capacity: usize,
+}
+
+impl<T> History<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            cursor: 0,
+            capacity,
+        }
+    }
+
+    /// Index of the current entry
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut T> {
+        self.entries.get_mut(self.cursor)
+    }
+
+    /// Records a state after the current one, dropping the states that were stepped back from
+    /// and the oldest state when full
+    pub fn push(&mut self, entry: T) {
+        self.entries.truncate(self.cursor + 1);
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+        self.cursor = self.entries.len() - 1;
+    }
+
+    /// Steps to the previous state, if any
+    pub fn back(&mut self) -> Option<&mut T> {
+        self.cursor = self.cursor.checked_sub(1)?;
+        self.entries.get_mut(self.cursor)
+    }
+
+    /// Steps to the next state, if any
+    pub fn forward(&mut self) -> Option<&mut T> {
+        if self.cursor + 1 >= self.entries.len() {
+            return None;
+        }
+        self.cursor += 1;
+        self.entries.get_mut(self.cursor)
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.iter_mut()
+    }
+}
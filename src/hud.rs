@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{BlendMode, WindowCanvas},
+};
+
+use crate::font::{LINE_HEIGHT, draw_text, text_width};
+
+/// Screen pixels per font pixel
+const SCALE: u32 = 2;
+/// Distance between the panel edges and its text in screen pixels
+const PADDING: i32 = 8;
+/// Period over which the rendering speed is averaged
+const RATE_INTERVAL: Duration = Duration::from_millis(500);
+/// Shortest time between refreshes of the lines, as gathering them holds up the workers
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Heads-up display of the view and render statistics
+pub struct Hud {
+    pub visible: bool,
+    /// Time spent on the last frame, excluding the sleep until the next one
+    pub frame_time: Duration,
+    rate_start: Instant,
+    rate_computed: u64,
+    pixels_per_second: f64,
+    /// Lines shown, and when they were last refreshed
+    lines: Vec<String>,
+    refreshed: Instant,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            frame_time: Duration::ZERO,
+            rate_start: Instant::now(),
+            rate_computed: 0,
+            pixels_per_second: 0.0,
+            lines: Vec::new(),
+            refreshed: Instant::now(),
+        }
+    }
+
+    /// Shows or hides the display, gathering fresh lines when it is shown again
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.lines.clear();
+    }
+
+    /// Whether the lines are due for a refresh
+    pub fn due(&self) -> bool {
+        self.lines.is_empty() || self.refreshed.elapsed() >= REFRESH_INTERVAL
+    }
+
+    /// Replaces the lines shown
+    ///
+    /// # Returns
+    /// Whether they changed, so the display needs to be drawn again
+    pub fn set_lines(&mut self, lines: Vec<String>) -> bool {
+        self.refreshed = Instant::now();
+        if lines == self.lines {
+            return false;
+        }
+        self.lines = lines;
+        true
+    }
+
+    /// Updates the rendering speed from the running count of computed pixels
+    pub fn measure(&mut self, computed: u64) {
+        let elapsed = self.rate_start.elapsed();
+        if elapsed < RATE_INTERVAL {
+            return;
+        }
+        let delta = computed.saturating_sub(self.rate_computed);
+        self.pixels_per_second = delta as f64 / elapsed.as_secs_f64();
+        self.rate_start = Instant::now();
+        self.rate_computed = computed;
+    }
+
+    pub fn pixels_per_second(&self) -> f64 {
+        self.pixels_per_second
+    }
+
+    /// Draws the lines on a translucent panel in the bottom-left corner
+    pub fn draw(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
+        let lines = &self.lines;
+        let (_, height) = canvas.output_size()?;
+        let line = (LINE_HEIGHT * SCALE) as i32;
+        let width = lines
+            .iter()
+            .map(|text| text_width(text, SCALE))
+            .max()
+            .unwrap_or(0);
+        let panel_height = lines.len() as i32 * line + PADDING;
+        let panel = Rect::new(
+            PADDING,
+            height as i32 - PADDING - panel_height,
+            width + 2 * PADDING as u32,
+            panel_height as u32,
+        );
+
+        let color = canvas.draw_color();
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 0xA0));
+        canvas.fill_rect(panel)?;
+        canvas.set_draw_color(Color::RGB(0xFF, 0xFF, 0xFF));
+        for (index, text) in lines.iter().enumerate() {
+            let top = panel.y() + PADDING + index as i32 * line;
+            draw_text(canvas, text, panel.x() + PADDING, top, SCALE)?;
+        }
+        canvas.set_draw_color(color);
+        Ok(())
+    }
+}
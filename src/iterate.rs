@@ -0,0 +1,557 @@
+use rug::{Assign, Float};
+
+use crate::{Arithmetic, Escape, Rect, dd::DoubleDouble, simd};
+
+/// Squared magnitude beyond which an orbit is known to escape
+pub const ESCAPE_RADIUS_SQ: f64 = 4.0;
+
+/// A point being iterated under z = z² + c in one tier of arithmetic
+///
+/// The escape loop and the orbit tracer are written once against this trait, so every tier
+/// takes the same steps and detects periods the same way.
+pub trait Orbit {
+    /// Squares the parts of z for the next step, returning its squared magnitude
+    fn mag_sq(&mut self) -> f64;
+    /// Applies the Mandelbrot iteration z = z² + c, reusing the squares from `mag_sq`
+    fn step(&mut self);
+    /// Whether z is within the period epsilon of the snapshot
+    fn returned(&mut self) -> bool;
+    /// Takes z as the new snapshot
+    fn snapshot(&mut self);
+    /// Offset of z from c, multiplied by 2^`exp`
+    fn offset(&mut self, exp: f32) -> (f64, f64);
+}
+
+/// Outcome of one step of Brent's cycle detection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrentStep {
+    /// The orbit returned to the snapshot after this many steps
+    Period(u32),
+    /// The interval ran out, so the snapshot should be retaken at the current z
+    Snapshot,
+    Continue,
+}
+
+/// Brent-style cycle detection: the orbit is compared against a snapshot taken at doubling
+/// intervals, so any cycle is found within a few times its length
+#[derive(Debug, Clone, Copy)]
+pub struct Brent {
+    interval: u32,
+    steps: u32,
+}
+
+impl Brent {
+    pub const fn new() -> Self {
+        Self {
+            interval: 1,
+            steps: 0,
+        }
+    }
+
+    /// Counts one iteration, given whether it brought the orbit back to the snapshot
+    pub fn step(&mut self, returned: bool) -> BrentStep {
+        self.steps += 1;
+        if returned {
+            return BrentStep::Period(self.steps);
+        }
+        if self.steps == self.interval {
+            self.interval = self.interval.saturating_mul(2);
+            self.steps = 0;
+            return BrentStep::Snapshot;
+        }
+        BrentStep::Continue
+    }
+}
+
+/// Iterates an orbit until it escapes, turns out periodic or reaches the limit
+///
+/// # Arguments
+/// * `orbit` - Orbit at its starting point
+/// * `max_iter` - Maximum number of iterations
+///
+/// # Returns
+/// How the orbit ended
+pub fn escape(orbit: &mut impl Orbit, max_iter: u32) -> Escape {
+    let mut brent = Brent::new();
+    for iter in 0..max_iter {
+        let mag_sq = orbit.mag_sq();
+        if mag_sq > ESCAPE_RADIUS_SQ {
+            return Escape::Escaped {
+                iter,
+                mag_sq: mag_sq as f32,
+            };
+        }
+        orbit.step();
+        match brent.step(orbit.returned()) {
+            BrentStep::Period(period) => return Escape::Interior { period },
+            BrentStep::Snapshot => orbit.snapshot(),
+            BrentStep::Continue => {}
+        }
+    }
+
+    // Point is likely in the Mandelbrot set, but no period was found
+    Escape::Limit
+}
+
+/// Follows a Mandelbrot orbit from c, which is its first point and is left out
+///
+/// # Arguments
+/// * `orbit` - Orbit of c starting from zero
+/// * `max_points` - Maximum number of points to return
+/// * `exp` - Binary exponent the offsets are multiplied by
+///
+/// # Returns
+/// Offsets of the following points from c times 2^`exp`, up to and including the first one
+/// that escapes
+pub fn trace(orbit: &mut impl Orbit, max_points: usize, exp: f32) -> Vec<(f64, f64)> {
+    let mut points = Vec::with_capacity(max_points);
+    orbit.mag_sq();
+    orbit.step();
+    while points.len() < max_points && orbit.mag_sq() <= ESCAPE_RADIUS_SQ {
+        orbit.step();
+        points.push(orbit.offset(exp));
+    }
+    points
+}
+
+/// Iterate the point at a fractional buffer position with the arithmetic the view needs
+pub fn get_pixel_escape(
+    x: f64,
+    y: f64,
+    rect: &Rect,
+    scale: &Float,
+    max_iter: u32,
+    scratch: &mut Scratch,
+) -> Escape {
+    rect.point_into(x, y, scale, &mut scratch.c_real, &mut scratch.c_imag);
+    let epsilon_exp = rect.period_epsilon_exp();
+    match rect.arithmetic() {
+        Arithmetic::F64 => escape_f64(
+            scratch.c_real.to_f64(),
+            scratch.c_imag.to_f64(),
+            max_iter,
+            (epsilon_exp as f64).exp2(),
+        ),
+        Arithmetic::DoubleDouble => escape_dd(
+            DoubleDouble::from_float(&scratch.c_real),
+            DoubleDouble::from_float(&scratch.c_imag),
+            max_iter,
+            (epsilon_exp as f64).exp2(),
+        ),
+        Arithmetic::Mpfr => escape_mpfr(scratch, max_iter, epsilon_exp),
+    }
+}
+
+/// Iterates the orbit of the point at a fractional buffer position with the arithmetic the view
+/// needs
+///
+/// The first point is `c` itself and is left out, as it is the start of the orbit.
+///
+/// # Returns
+/// Offsets of the following points from `c` in buffer pixels, before turning by the view
+/// angle, up to and including the first one that escapes
+pub fn trace_orbit(
+    x: f64,
+    y: f64,
+    rect: &Rect,
+    scale: &Float,
+    max_points: usize,
+    scratch: &mut Scratch,
+) -> Vec<(f64, f64)> {
+    rect.point_into(x, y, scale, &mut scratch.c_real, &mut scratch.c_imag);
+    let epsilon_exp = rect.period_epsilon_exp();
+    let points = match rect.arithmetic() {
+        Arithmetic::F64 => {
+            let mut orbit = F64Orbit::new(
+                scratch.c_real.to_f64(),
+                scratch.c_imag.to_f64(),
+                (epsilon_exp as f64).exp2(),
+            );
+            trace(&mut orbit, max_points, rect.scale_exp)
+        }
+        Arithmetic::DoubleDouble => {
+            let mut orbit = DdOrbit::new(
+                DoubleDouble::from_float(&scratch.c_real),
+                DoubleDouble::from_float(&scratch.c_imag),
+                (epsilon_exp as f64).exp2(),
+            );
+            trace(&mut orbit, max_points, rect.scale_exp)
+        }
+        Arithmetic::Mpfr => {
+            let mut orbit = MpfrOrbit::new(scratch, epsilon_exp);
+            trace(&mut orbit, max_points, rect.scale_exp)
+        }
+    };
+
+    // Offsets in the complex plane are 3 * scale per pixel
+    points
+        .into_iter()
+        .map(|(x, y)| (x / 3.0, y / 3.0))
+        .collect()
+}
+
+/// Iterates a point in f64, skipping the loop for known interior points
+pub fn escape_f64(c_real: f64, c_imag: f64, max_iter: u32, epsilon: f64) -> Escape {
+    if let Some(period) = interior_period(c_real, c_imag) {
+        return Escape::Interior { period };
+    }
+    escape(&mut F64Orbit::new(c_real, c_imag, epsilon), max_iter)
+}
+
+/// Iterates a batch of points in f64 with vector instructions, skipping the loop for known
+/// interior points
+pub fn escapes_f64(points: &[(f64, f64)], max_iter: u32, epsilon: f64) -> Vec<Escape> {
+    let mut escapes: Vec<Escape> = points
+        .iter()
+        .map(|&(real, imag)| match interior_period(real, imag) {
+            Some(period) => Escape::Interior { period },
+            None => Escape::Limit,
+        })
+        .collect();
+    let (indices, pending): (Vec<usize>, Vec<(f64, f64)>) = points
+        .iter()
+        .enumerate()
+        .filter(|&(index, _)| escapes[index] == Escape::Limit)
+        .map(|(index, &point)| (index, point))
+        .unzip();
+    for (index, escape) in indices
+        .into_iter()
+        .zip(simd::escape_batch(&pending, max_iter, epsilon))
+    {
+        escapes[index] = escape;
+    }
+    escapes
+}
+
+/// Iterates a point in double-double, skipping the loop for known interior points
+pub fn escape_dd(
+    c_real: DoubleDouble,
+    c_imag: DoubleDouble,
+    max_iter: u32,
+    epsilon: f64,
+) -> Escape {
+    if let Some(period) = interior_period_dd(c_real, c_imag) {
+        return Escape::Interior { period };
+    }
+    escape(&mut DdOrbit::new(c_real, c_imag, epsilon), max_iter)
+}
+
+/// Iterates the point in `scratch.c_real` and `scratch.c_imag` without allocating
+pub fn escape_mpfr(scratch: &mut Scratch, max_iter: u32, epsilon_exp: i32) -> Escape {
+    escape(&mut MpfrOrbit::new(scratch, epsilon_exp), max_iter)
+}
+
+/// Analytic test for the main cardioid and the period-2 bulb
+fn interior_period(real: f64, imag: f64) -> Option<u32> {
+    let imag_sq = imag * imag;
+    let q = (real - 0.25) * (real - 0.25) + imag_sq;
+    if q * (q + (real - 0.25)) <= 0.25 * imag_sq {
+        return Some(1);
+    }
+    if (real + 1.0) * (real + 1.0) + imag_sq <= 0.0625 {
+        return Some(2);
+    }
+    None
+}
+
+/// Analytic test for the main cardioid and the period-2 bulb in double-double, where f64
+/// would misjudge points near their boundaries
+fn interior_period_dd(real: DoubleDouble, imag: DoubleDouble) -> Option<u32> {
+    let quarter = DoubleDouble::new(0.25);
+    let imag_sq = imag.square();
+    let shifted = real - quarter;
+    let q = shifted.square() + imag_sq;
+    if (q * (q + shifted) - quarter * imag_sq).hi <= 0.0 {
+        return Some(1);
+    }
+    if ((real + DoubleDouble::new(1.0)).square() + imag_sq).hi <= 0.0625 {
+        return Some(2);
+    }
+    None
+}
+
+/// Orbit in f64, for views shallow enough that its mantissa tells pixels apart
+pub struct F64Orbit {
+    c_real: f64,
+    c_imag: f64,
+    z_real: f64,
+    z_imag: f64,
+    real_sq: f64,
+    imag_sq: f64,
+    check_real: f64,
+    check_imag: f64,
+    epsilon: f64,
+}
+
+impl F64Orbit {
+    /// Orbit of c starting from zero, as the Mandelbrot set is defined
+    pub fn new(c_real: f64, c_imag: f64, epsilon: f64) -> Self {
+        Self::starting_at(0.0, 0.0, c_real, c_imag, epsilon)
+    }
+
+    /// Orbit of c starting from an arbitrary z, as Julia sets are defined
+    pub fn starting_at(z_real: f64, z_imag: f64, c_real: f64, c_imag: f64, epsilon: f64) -> Self {
+        Self {
+            c_real,
+            c_imag,
+            z_real,
+            z_imag,
+            real_sq: 0.0,
+            imag_sq: 0.0,
+            check_real: z_real,
+            check_imag: z_imag,
+            epsilon,
+        }
+    }
+}
+
+impl Orbit for F64Orbit {
+    fn mag_sq(&mut self) -> f64 {
+        self.real_sq = self.z_real * self.z_real;
+        self.imag_sq = self.z_imag * self.z_imag;
+        self.real_sq + self.imag_sq
+    }
+
+    fn step(&mut self) {
+        self.z_imag = 2.0 * self.z_real * self.z_imag + self.c_imag;
+        self.z_real = self.real_sq - self.imag_sq + self.c_real;
+    }
+
+    fn returned(&mut self) -> bool {
+        (self.z_real - self.check_real).abs() < self.epsilon
+            && (self.z_imag - self.check_imag).abs() < self.epsilon
+    }
+
+    fn snapshot(&mut self) {
+        self.check_real = self.z_real;
+        self.check_imag = self.z_imag;
+    }
+
+    fn offset(&mut self, exp: f32) -> (f64, f64) {
+        let factor = (exp as f64).exp2();
+        (
+            (self.z_real - self.c_real) * factor,
+            (self.z_imag - self.c_imag) * factor,
+        )
+    }
+}
+
+/// Orbit in double-double, for views past the f64 mantissa
+pub struct DdOrbit {
+    c_real: DoubleDouble,
+    c_imag: DoubleDouble,
+    z_real: DoubleDouble,
+    z_imag: DoubleDouble,
+    real_sq: DoubleDouble,
+    imag_sq: DoubleDouble,
+    check_real: DoubleDouble,
+    check_imag: DoubleDouble,
+    epsilon: f64,
+}
+
+impl DdOrbit {
+    /// Orbit of c starting from zero
+    pub fn new(c_real: DoubleDouble, c_imag: DoubleDouble, epsilon: f64) -> Self {
+        Self {
+            c_real,
+            c_imag,
+            z_real: DoubleDouble::default(),
+            z_imag: DoubleDouble::default(),
+            real_sq: DoubleDouble::default(),
+            imag_sq: DoubleDouble::default(),
+            check_real: DoubleDouble::default(),
+            check_imag: DoubleDouble::default(),
+            epsilon,
+        }
+    }
+}
+
+impl Orbit for DdOrbit {
+    fn mag_sq(&mut self) -> f64 {
+        self.real_sq = self.z_real.square();
+        self.imag_sq = self.z_imag.square();
+        self.real_sq.hi + self.imag_sq.hi
+    }
+
+    fn step(&mut self) {
+        self.z_imag = (self.z_real * self.z_imag).double() + self.c_imag;
+        self.z_real = self.real_sq - self.imag_sq + self.c_real;
+    }
+
+    fn returned(&mut self) -> bool {
+        (self.z_real - self.check_real).abs().hi < self.epsilon
+            && (self.z_imag - self.check_imag).abs().hi < self.epsilon
+    }
+
+    fn snapshot(&mut self) {
+        self.check_real = self.z_real;
+        self.check_imag = self.z_imag;
+    }
+
+    fn offset(&mut self, exp: f32) -> (f64, f64) {
+        let factor = (exp as f64).exp2();
+        (
+            (self.z_real - self.c_real).hi * factor,
+            (self.z_imag - self.c_imag).hi * factor,
+        )
+    }
+}
+
+/// Orbit in MPFR at the precision of the scratch values, for views past double-double
+///
+/// Iterates the point in `scratch.c_real` and `scratch.c_imag`, keeping every intermediate in
+/// the scratch so no step allocates.
+pub struct MpfrOrbit<'a> {
+    scratch: &'a mut Scratch,
+    epsilon_exp: i32,
+}
+
+impl<'a> MpfrOrbit<'a> {
+    /// Orbit of the scratch point starting from zero
+    pub fn new(scratch: &'a mut Scratch, epsilon_exp: i32) -> Self {
+        scratch.z_real.assign(0);
+        scratch.z_imag.assign(0);
+        scratch.check_real.assign(0);
+        scratch.check_imag.assign(0);
+        Self {
+            scratch,
+            epsilon_exp,
+        }
+    }
+}
+
+impl Orbit for MpfrOrbit<'_> {
+    fn mag_sq(&mut self) -> f64 {
+        let Scratch {
+            z_real,
+            z_imag,
+            real_sq,
+            imag_sq,
+            mag_sq,
+            ..
+        } = &mut *self.scratch;
+        real_sq.assign(z_real.square_ref());
+        imag_sq.assign(z_imag.square_ref());
+        mag_sq.assign(&*real_sq + &*imag_sq);
+        mag_sq.to_f64()
+    }
+
+    fn step(&mut self) {
+        let Scratch {
+            c_real,
+            c_imag,
+            z_real,
+            z_imag,
+            real_sq,
+            imag_sq,
+            ..
+        } = &mut *self.scratch;
+        // z_imag = 2.0 * z_real * z_imag + c_imag;
+        *z_real <<= 1;
+        z_imag.mul_add_mut(z_real, c_imag);
+        // z_real = real_sq - imag_sq + c_real;
+        z_real.assign(&*real_sq - &*imag_sq);
+        *z_real += &*c_real;
+    }
+
+    fn returned(&mut self) -> bool {
+        let Scratch {
+            z_real,
+            z_imag,
+            check_real,
+            check_imag,
+            check_diff,
+            ..
+        } = &mut *self.scratch;
+        let epsilon_exp = self.epsilon_exp;
+        check_diff.assign(&*z_real - &*check_real);
+        if check_diff.get_exp().is_some_and(|exp| exp >= epsilon_exp) {
+            return false;
+        }
+        check_diff.assign(&*z_imag - &*check_imag);
+        check_diff.get_exp().is_none_or(|exp| exp < epsilon_exp)
+    }
+
+    fn snapshot(&mut self) {
+        let scratch = &mut *self.scratch;
+        scratch.check_real.assign(&scratch.z_real);
+        scratch.check_imag.assign(&scratch.z_imag);
+    }
+
+    fn offset(&mut self, exp: f32) -> (f64, f64) {
+        // Scale in the exponent, as the offset itself may be below the range of f64
+        let Scratch {
+            c_real,
+            c_imag,
+            z_real,
+            z_imag,
+            check_diff,
+            ..
+        } = &mut *self.scratch;
+        let mut scaled = |value: &Float, origin: &Float| {
+            check_diff.assign(value - origin);
+            let (mantissa, value_exp) = check_diff.to_f64_exp();
+            mantissa * (value_exp as f64 + exp as f64).exp2()
+        };
+        (scaled(z_real, c_real), scaled(z_imag, c_imag))
+    }
+}
+
+/// Per-thread MPFR values reused across points, so iterating does not allocate
+pub struct Scratch {
+    pub c_real: Float,
+    pub c_imag: Float,
+    pub c_step_real: Float,
+    pub c_step_imag: Float,
+    pub z_real: Float,
+    pub z_imag: Float,
+    pub real_sq: Float,
+    pub imag_sq: Float,
+    pub mag_sq: Float,
+    pub check_real: Float,
+    pub check_imag: Float,
+    pub check_diff: Float,
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        let value = Float::new(64);
+        Self {
+            c_real: value.clone(),
+            c_imag: value.clone(),
+            c_step_real: value.clone(),
+            c_step_imag: value.clone(),
+            z_real: value.clone(),
+            z_imag: value.clone(),
+            real_sq: value.clone(),
+            imag_sq: value.clone(),
+            mag_sq: value.clone(),
+            check_real: value.clone(),
+            check_imag: value.clone(),
+            check_diff: value,
+        }
+    }
+
+    /// Changes the precision of all values, only reallocating when it differs
+    pub fn set_prec(&mut self, prec: u32) {
+        if self.c_real.prec() == prec {
+            return;
+        }
+        for value in [
+            &mut self.c_real,
+            &mut self.c_imag,
+            &mut self.c_step_real,
+            &mut self.c_step_imag,
+            &mut self.z_real,
+            &mut self.z_imag,
+            &mut self.real_sq,
+            &mut self.imag_sq,
+            &mut self.mag_sq,
+            &mut self.check_real,
+            &mut self.check_imag,
+            &mut self.check_diff,
+        ] {
+            value.set_prec(prec);
+        }
+    }
+}
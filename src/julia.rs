@@ -0,0 +1,61 @@
+use crate::{
+    Palette,
+    iterate::{self, F64Orbit},
+};
+
+/// Width and height of the Julia preview in pixels
+pub const JULIA_SIZE: u32 = 160;
+/// Iteration limit of the preview, which favors speed over detail
+pub const JULIA_MAX_ITER: u32 = 500;
+/// Half the width of the square of starting points around the origin
+const JULIA_RADIUS: f64 = 1.6;
+/// Distance below which an orbit is considered periodic, well below the preview's pixel size
+const JULIA_EPSILON: f64 = 1e-10;
+
+/// A Julia preview to be computed for the point `c`
+#[derive(Debug, Clone, Copy)]
+pub struct JuliaRequest {
+    pub c_real: f64,
+    pub c_imag: f64,
+    pub max_iter: u32,
+    /// Increasing number identifying the request, so outdated images can be skipped
+    pub id: u64,
+}
+
+/// A computed Julia preview
+pub struct JuliaImage {
+    pub id: u64,
+    pub data: Vec<[u8; 4]>,
+}
+
+/// Computes the Julia set of a request at the preview size
+///
+/// # Arguments
+/// * `request` - Point `c` and iteration limit
+/// * `palette` - Colors of escaped and interior points, shared with the main view
+///
+/// # Returns
+/// An image of `JULIA_SIZE` by `JULIA_SIZE` pixels
+pub fn render(request: &JuliaRequest, palette: &Palette) -> JuliaImage {
+    let step = 2.0 * JULIA_RADIUS / JULIA_SIZE as f64;
+    let data = (0..JULIA_SIZE * JULIA_SIZE)
+        .map(|index| {
+            let x = (index % JULIA_SIZE) as f64 + 0.5;
+            let y = (index / JULIA_SIZE) as f64 + 0.5;
+            let mut orbit = F64Orbit::starting_at(
+                x * step - JULIA_RADIUS,
+                y * step - JULIA_RADIUS,
+                request.c_real,
+                request.c_imag,
+                JULIA_EPSILON,
+            );
+            let escape = iterate::escape(&mut orbit, request.max_iter);
+            let (r, g, b) = palette.color(escape);
+            [r, g, b, 0xFF]
+        })
+        .collect();
+    JuliaImage {
+        id: request.id,
+        data,
+    }
+}
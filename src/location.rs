@@ -0,0 +1,161 @@
+use rug::Float;
+
+/// Deepest zoom in bits, around 1e30000 and far beyond what can be rendered in practice, which
+/// keeps the MPFR precision derived from it reasonable
+const MAX_DEPTH: f32 = 100_000.0;
+
+/// A view independent of the window: complex center, zoom depth in bits below the initial
+/// view and rotation in degrees
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub real: Float,
+    pub imag: Float,
+    pub depth: f32,
+    pub angle: f32,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Self {
+            real: Float::new(64),
+            imag: Float::new(64),
+            depth: 0.0,
+            angle: 0.0,
+        }
+    }
+}
+
+/// Parses a decimal number keeping all of its digits
+pub fn parse_float(value: &str) -> Result<Float, String> {
+    // Each decimal digit needs a little over 3.3 bits
+    let prec = (value.len() as u32 * 10 / 3).max(64);
+    let parsed = Float::parse(value.trim()).map_err(|_| format!("invalid number '{value}'"))?;
+    let parsed = Float::with_val(prec, parsed);
+    if !parsed.is_finite() {
+        return Err(format!("number must be finite, got '{value}'"));
+    }
+    Ok(parsed)
+}
+
+/// Parses a magnification like `1e20` into a depth in bits
+pub fn parse_zoom(value: &str) -> Result<f32, String> {
+    let zoom = parse_float(value)?;
+    if !zoom.is_sign_positive() || zoom.is_zero() {
+        return Err(format!("zoom must be positive, got '{value}'"));
+    }
+    check_depth(zoom.log2().to_f32()).map_err(|_| format!("zoom out of range, got '{value}'"))
+}
+
+/// Parses a depth in bits, as written to session and recording files
+pub fn parse_depth(value: &str) -> Result<f32, String> {
+    let depth = value
+        .trim()
+        .parse::<f32>()
+        .map_err(|_| format!("invalid depth '{value}'"))?;
+    check_depth(depth)
+}
+
+/// Checks that a depth is finite and within `MAX_DEPTH` bits in either direction
+fn check_depth(depth: f32) -> Result<f32, String> {
+    if !depth.is_finite() || depth.abs() > MAX_DEPTH {
+        return Err(format!(
+            "depth must be between -{MAX_DEPTH} and {MAX_DEPTH} bits"
+        ));
+    }
+    Ok(depth)
+}
+
+/// Parses a rotation in degrees, normalized to `0..360`
+pub fn parse_angle(value: &str) -> Result<f32, String> {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|angle| angle.is_finite())
+        .map(|angle| angle.rem_euclid(360.0))
+        .ok_or_else(|| format!("invalid angle '{value}'"))
+}
+
+/// Formats a depth in bits as a magnification like `1.50e20`, with `digits` decimals
+pub fn format_zoom(depth: f32, digits: usize) -> String {
+    let ooms = depth as f64 * 2.0_f64.log10();
+    let exponent = ooms.floor();
+    format!("{:.*}e{}", digits, 10.0_f64.powf(ooms - exponent), exponent)
+}
+
+/// Formats a coordinate with enough digits to tell pixels apart at a given depth
+pub fn format_coordinate(value: &Float, depth: f32) -> String {
+    const EXTRA_DIGITS: usize = 6;
+    let digits = (depth.max(0.0) * 2.0_f32.log10()) as usize + EXTRA_DIGITS;
+    value.to_string_radix(10, Some(digits))
+}
+
+/// Bits between our depth and the Kalles Fraktaler zoom, where zoom 1 spans 4 units instead of 3
+fn kfr_depth_offset() -> f32 {
+    (4.0_f32 / 3.0).log2()
+}
+
+/// Formats a location and iteration limit like a Kalles Fraktaler (KFR) location file
+///
+/// The zoom is converted to the KFR convention only here and in `parse_kfr`, at the clipboard
+/// boundary, while the HUD, the go-to dialog and sessions show our own zoom.
+pub fn format_kfr(location: &Location, max_iter: u32) -> String {
+    format!(
+        "Re: {}\r\nIm: {}\r\nZoom: {}\r\nIterations: {}\r\nRotateAngle: {}\r\n",
+        format_coordinate(&location.real, location.depth),
+        format_coordinate(&location.imag, location.depth),
+        format_zoom(location.depth + kfr_depth_offset(), 6),
+        max_iter,
+        location.angle
+    )
+}
+
+/// Parses a location in the Kalles Fraktaler (KFR) style
+///
+/// Accepts `key: value` or `key = value` lines with keys `re`, `im`, `zoom` and optionally
+/// `iterations` and `rotateangle`, in any case and order, or just the values separated by
+/// whitespace in that order. Unknown keys are ignored, so whole KFR files can be pasted.
+///
+/// # Returns
+/// The location and the iteration limit, if given
+pub fn parse_kfr(text: &str) -> Result<(Location, Option<u32>), String> {
+    let mut values: [Option<&str>; 5] = [None; 5];
+    let pairs: Vec<(&str, &str)> = text
+        .lines()
+        .filter_map(|line| line.split_once(':').or_else(|| line.split_once('=')))
+        .collect();
+    if pairs.is_empty() {
+        for (slot, value) in values.iter_mut().zip(text.split_whitespace()) {
+            *slot = Some(value);
+        }
+    }
+    for (key, value) in pairs {
+        let slot = match key.trim().to_ascii_lowercase().as_str() {
+            "re" | "real" => 0,
+            "im" | "imag" => 1,
+            "zoom" => 2,
+            "iterations" | "iter" | "max_iter" => 3,
+            "rotateangle" | "angle" => 4,
+            _ => continue,
+        };
+        values[slot] = Some(value.trim());
+    }
+
+    let [Some(real), Some(imag), Some(zoom), iterations, angle] = values else {
+        return Err("expected Re, Im and Zoom".to_string());
+    };
+    let location = Location {
+        real: parse_float(real)?,
+        imag: parse_float(imag)?,
+        depth: parse_zoom(zoom)? - kfr_depth_offset(),
+        angle: angle.map(parse_angle).transpose()?.unwrap_or(0.0),
+    };
+    let iterations = iterations
+        .map(|value| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid iterations '{value}'"))
+        })
+        .transpose()?;
+    Ok((location, iterations.map(|value| value.max(1))))
+}
@@ -1,25 +1,64 @@
-use rug::Float;
+use rug::{Assign, Float};
 use sdl2::{
-    EventPump,
+    EventPump, GameControllerSubsystem,
+    clipboard::ClipboardUtil,
+    controller::{Axis, GameController},
     event::{Event, WindowEvent},
-    keyboard::Keycode,
+    keyboard::{Keycode, Mod},
     mouse::MouseButton,
+    pixels::{Color, PixelFormatEnum},
     rect::{FPoint, FRect},
     render::{BlendMode, Texture},
+    surface::Surface,
     sys,
 };
 use std::{
     error::Error,
-    fmt,
-    sync::{Arc, Mutex},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+mod config;
+mod dd;
+mod dialog;
+mod font;
+mod history;
+mod hud;
+mod iterate;
+mod julia;
+mod location;
+mod minimap;
 mod pixel;
+mod recording;
+mod session;
+mod simd;
+mod tile;
+use dd::DoubleDouble;
+use dialog::{Dialog, DialogAction};
+use history::History;
+use hud::Hud;
+use iterate::Scratch;
+use julia::{JULIA_MAX_ITER, JULIA_SIZE, JuliaImage, JuliaRequest};
+use location::{
+    Location, format_coordinate, format_kfr, format_zoom, parse_float, parse_kfr, parse_zoom,
+};
+use minimap::{MINIMAP_SIZE, Minimap, MinimapImage, MinimapRequest, NESTED_LEVELS};
 use pixel::{
-    Point32, ScaleDirection, Size32, extend_buffer, hsv_to_rgb, scale_rect, translate_rect,
+    Filter, Point32, ScaleDirection, Size32, color_distance, downsample, downsample_into,
+    extend_buffer, hsv_to_rgb, linear_to_srgb, rotate_rect, scale_rect, srgb_to_linear,
+    subsample_offset, translate_rect,
 };
+use recording::{Frame, Recorder, frame_at};
+use tile::{Job, Tile, TileQueue};
+
+/// Layout of the `[r, g, b, a]` pixels of buffers, for textures and exported images alike
+const PIXEL_FORMAT: PixelFormatEnum = PixelFormatEnum::RGBA32;
 
 #[derive(Debug, Clone, Copy)]
 struct Config {
@@ -27,6 +66,10 @@ struct Config {
     window_size: Size32,
     /// Anti-aliasing multiplier for the render buffer
     aliasing_factor: u32,
+    /// Maximum number of extra samples for pixels along edges, 0 to disable
+    adaptive_samples: u32,
+    /// Filter for downsampling the anti-aliased render buffer
+    downsample_filter: Filter,
     /// Speed multiplier for zooming in/out
     zoom_factor: f32,
     /// Target frames per second for the main loop
@@ -37,17 +80,23 @@ struct Config {
     motion_decay: f32,
     /// Maximum number of iterations for escape calculation
     max_iter: u32,
+    /// Derive the maximum number of iterations from the view instead of `max_iter`
+    auto_iter: bool,
     /// Iteration divisor for color cycling
     color_cycle: u32,
     /// Color saturation (HSV)
     saturation: f32,
+    /// Brightness of interior pixels, colored by their detected period
+    interior_value: f32,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             window_size: Size32 { w: 800, h: 600 },
-            aliasing_factor: 2,
+            aliasing_factor: 1,
+            adaptive_samples: 8,
+            downsample_filter: Filter::Mitchell,
             zoom_factor: 0.01,
             target_fps: 60.0,
             worker_threads: thread::available_parallelism()
@@ -55,8 +104,10 @@ impl Default for Config {
                 .unwrap_or(4),
             motion_decay: 0.9,
             max_iter: 10000,
+            auto_iter: true,
             color_cycle: 10,
             saturation: 0.8,
+            interior_value: 0.0,
         }
     }
 }
@@ -69,23 +120,34 @@ impl Config {
         }
     }
 
-    fn buffer_pitch(&self) -> u32 {
-        self.window_size.w * self.aliasing_factor * 4
-    }
-
-    fn buffer_length(&self) -> u32 {
-        self.window_size.w * self.window_size.h * self.aliasing_factor * self.aliasing_factor * 4
+    fn window_pitch(&self) -> u32 {
+        self.window_size.w * 4
     }
 
     fn target_frame_duration(&self) -> Duration {
         Duration::from_secs_f32(1.0 / self.target_fps)
     }
+
+    /// Scale exponent of the initial view, where the zoom level is 10^0
+    fn scale_offset(&self) -> f32 {
+        let min_size = self.window_size.w.min(self.window_size.h);
+        (min_size as f32 * self.aliasing_factor as f32).log2()
+    }
+
+    fn palette(&self) -> Palette {
+        Palette {
+            color_cycle: self.color_cycle,
+            saturation: self.saturation,
+            interior_value: self.interior_value,
+        }
+    }
 }
 
 #[derive(Debug)]
 enum AppError {
     SdlError(String),
     IoError(std::io::Error),
+    InvalidConfig(String),
 }
 
 impl fmt::Display for AppError {
@@ -93,6 +155,7 @@ impl fmt::Display for AppError {
         match self {
             Self::SdlError(msg) => write!(f, "SDL error: {msg}"),
             Self::IoError(e) => write!(f, "IO error: {e}"),
+            Self::InvalidConfig(msg) => write!(f, "Config error: {msg}"),
         }
     }
 }
@@ -117,35 +180,429 @@ struct App {
     canvas: Canvas,
     input: Input,
     update_title: bool,
+    pixels_distinct: bool,
+    history: History<HistoryEntry>,
+    navigating: bool,
+    dialog: Option<Dialog>,
+    hud: Hud,
+    minimap: Minimap,
+    /// Orbit of the point under the mouse, while orbit mode is on
+    orbit: Option<Orbit>,
+    /// Julia preview of the point under the mouse, while shown
+    julia: Option<JuliaInset>,
+    /// Open game controllers, which only send events while kept
+    controllers: Vec<GameController>,
+    /// Recording of the views shown, while the R key has it on
+    recorder: Option<Recorder>,
+    /// Recording driving the view, until its last frame
+    replay: Option<Replay>,
+}
+
+/// Playback of a recording in the window
+struct Replay {
+    frames: Vec<Frame>,
+    /// When the first frame was shown
+    start: Option<Instant>,
+    /// Index of the frame shown
+    shown: Option<usize>,
+    /// Whether the iteration limit was selected automatically before the replay took over
+    auto_iter: bool,
+}
+
+/// State of the Julia preview inset
+struct JuliaInset {
+    /// Buffer pixel and view generation of the last request
+    pixel: Point32,
+    generation: u64,
+    /// Identifiers of the last requested and the displayed image
+    requested: u64,
+    shown: u64,
+}
+
+/// Iterations of the point under the mouse, for drawing over the view
+struct Orbit {
+    /// Buffer pixel and view generation of the last request
+    pixel: Point32,
+    generation: u64,
+    /// Identifiers of the last requested and the displayed trace
+    requested: u64,
+    shown: u64,
+    /// Orbit points in buffer pixels of the displayed trace, starting with the traced pixel
+    points: Vec<FPoint>,
+    /// Classification of the point, `None` until first traced
+    escape: Option<Escape>,
+    max_iter: u32,
+}
+
+/// An orbit to be traced for a buffer pixel of a view
+struct OrbitRequest {
+    rect: Rect,
+    pixel: Point32,
+    max_iter: u32,
+    /// Increasing number identifying the request, so outdated traces can be skipped
+    id: u64,
+}
+
+/// A traced orbit, with the classification of its starting point
+struct OrbitTrace {
+    id: u64,
+    points: Vec<FPoint>,
+    escape: Escape,
+    max_iter: u32,
+}
+
+impl OrbitRequest {
+    /// Classifies and traces the point in the same arithmetic as the render
+    fn trace(&self, scratch: &mut Scratch) -> OrbitTrace {
+        const ORBIT_POINTS: usize = 2000;
+        let rect = &self.rect;
+        let (x, y) = (self.pixel.x as f64, self.pixel.y as f64);
+        let scale = rect.scale();
+        scratch.set_prec(rect.precision());
+        let escape = iterate::get_pixel_escape(x, y, rect, &scale, self.max_iter, scratch);
+
+        // Turn the offsets back into buffer pixels, after the pixel the orbit starts from
+        let (sin, cos) = rect.rotation();
+        let max_points = ORBIT_POINTS.min(self.max_iter as usize);
+        let offsets = iterate::trace_orbit(x, y, rect, &scale, max_points, scratch);
+        let points = std::iter::once((0.0, 0.0))
+            .chain(offsets)
+            .map(|(dx, dy)| {
+                FPoint::new(
+                    (x + cos * dx + sin * dy) as f32,
+                    (y + cos * dy - sin * dx) as f32,
+                )
+            })
+            .collect();
+        OrbitTrace {
+            id: self.id,
+            points,
+            escape,
+            max_iter: self.max_iter,
+        }
+    }
+}
+
+/// A visited view, with the buffer it was left with while recent
+struct HistoryEntry {
+    location: Location,
+    max_iter: u32,
+    snapshot: Option<Snapshot>,
+}
+
+/// Buffer contents and canvas placement of a view, to show it again without recomputing
+struct Snapshot {
+    rect: Rect,
+    size: Size32,
+    data: Vec<[u8; 4]>,
+    state: Vec<PixelState>,
+    max_iter: u32,
+    offset: FPoint,
+    scale: f32,
 }
 
 struct Buffer {
     size: Size32,
-    data: Vec<u8>,
+    data: Vec<[u8; 4]>,
+    state: Vec<PixelState>,
     rect: Rect,
-    progress: u32,
+    queue: TileQueue,
+    generation: Arc<AtomicU64>,
+    wake: Arc<Condvar>,
+    /// Signalled when the workers have no job left, for waiting until the buffer is done
+    idle: Arc<Condvar>,
+    focus: Point32,
+    pending: u32,
+    resolved: u32,
+    /// Jobs taken by workers and not yet stored
+    active: u32,
+    /// Running count of computed pixels, for measuring rendering speed
+    computed: u64,
+    /// Julia preview waiting for an idle worker
+    julia_request: Option<JuliaRequest>,
+    julia_image: Option<JuliaImage>,
+    /// Identifier of the last Julia request, which keeps increasing when the preview is toggled
+    julia_id: u64,
+    /// Orbit of the point under the mouse waiting for an idle worker, and the last one traced
+    orbit_request: Option<OrbitRequest>,
+    orbit_trace: Option<OrbitTrace>,
+    /// Identifier of the last orbit request
+    orbit_id: u64,
+    /// Minimap levels waiting for an idle worker, and those computed
+    minimap_requests: Vec<MinimapRequest>,
+    minimap_images: Vec<MinimapImage>,
+    refining: bool,
     max_iter: u32,
+    auto_iter: bool,
+    samples: u32,
+    retitle: bool,
     flush: bool,
+    /// Workers stored pixels since the texture was updated, which unlike `flush` is throttled
+    stored: bool,
     exit: bool,
 }
 
+/// Snapshot of the view and settings that determine pixel colors
+struct Render {
+    rect: Rect,
+    max_iter: u32,
+    palette: Palette,
+    samples: u32,
+}
+
+/// Render state of a single buffer pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum PixelState {
+    /// Not computed for the current view and iteration limit
+    #[default]
+    Empty,
+    /// Escaped or found to be interior
+    Done,
+    /// Reached the iteration limit, to be recomputed when the limit is raised
+    Limit,
+    /// Supersampled because it lies on an edge
+    Refined,
+}
+
+impl Buffer {
+    /// Sample spacing of the first, coarsest pass
+    const COARSE_STEP: u32 = 8;
+
+    /// Start a new pass over all pixels that are not valid for the current view
+    ///
+    /// Tiles are computed outward from `focus`, in buffer pixels.
+    fn restart(&mut self, focus: Point32) {
+        self.focus = focus;
+        self.refining = false;
+        self.schedule();
+    }
+
+    /// Wake threads waiting in `wait_idle` once no job is queued or in progress
+    fn signal_idle(&self) {
+        if self.active == 0 && self.queue.is_empty() {
+            self.idle.notify_all();
+        }
+    }
+
+    /// Start a new pass over pixels that reached the iteration limit
+    fn refine(&mut self, max_iter: u32) {
+        self.set_max_iter(max_iter);
+        self.refining = true;
+        self.retitle = true;
+        self.schedule();
+    }
+
+    /// Change the iteration limit, invalidating the pixels it affects
+    fn set_max_iter(&mut self, max_iter: u32) {
+        if max_iter < self.max_iter {
+            // Escaped pixels may now exceed the limit
+            self.state.fill(PixelState::Empty);
+        } else if max_iter > self.max_iter {
+            // Supersampled pixels may include samples that reached the limit
+            for state in self.state.iter_mut() {
+                if matches!(*state, PixelState::Limit | PixelState::Refined) {
+                    *state = PixelState::Empty;
+                }
+            }
+        }
+        self.max_iter = max_iter;
+    }
+
+    /// Update pixel states after scaling the buffer
+    ///
+    /// Scaling up invalidates pixels that were duplicated, keeping the even pixel of each 2x2
+    /// block that was computed at its own position. Supersampled pixels keep their color, but
+    /// are refined again at the new pixel size.
+    fn rescale_state(&mut self, direction: ScaleDirection) {
+        let width = self.size.w as usize;
+        for (index, state) in self.state.iter_mut().enumerate() {
+            if direction == ScaleDirection::Up
+                && ((index % width) % 2 == 1 || (index / width) % 2 == 1)
+            {
+                *state = PixelState::Empty;
+            } else if *state == PixelState::Refined {
+                *state = PixelState::Done;
+            }
+        }
+    }
+
+    /// Cancel work in progress and queue all tiles for a new generation
+    fn schedule(&mut self) {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.pending = self
+            .queue
+            .reset(self.size, Self::COARSE_STEP, generation, self.focus);
+        self.resolved = 0;
+        self.wake.notify_all();
+    }
+
+    /// Snapshot the parameters needed to compute pixels
+    fn render(&self, palette: Palette) -> Render {
+        Render {
+            rect: self.rect.clone(),
+            max_iter: self.max_iter,
+            palette,
+            samples: self.samples,
+        }
+    }
+
+    /// Store newly computed samples of a tile, filling their blocks of empty pixels
+    fn store_tile(&mut self, job: &Job, tile_buffer: &[[u8; 4]], tile_state: &[PixelState]) {
+        let width = self.size.w as usize;
+        let step = job.step as usize;
+        let tile = job.tile;
+        let (left, right) = (tile.x as usize, (tile.x + tile.w) as usize);
+        let (top, bottom) = (tile.y as usize, (tile.y + tile.h) as usize);
+        for y in (top..bottom).step_by(step) {
+            for x in (left..right).step_by(step) {
+                let local = (y - top) * tile.w as usize + x - left;
+                let sample = y * width + x;
+                if self.state[sample] != PixelState::Empty || tile_state[local] == PixelState::Empty
+                {
+                    continue;
+                }
+                let color = tile_buffer[local];
+                for block_y in y..(y + step).min(bottom) {
+                    let row = block_y * width;
+                    for index in row + x..row + (x + step).min(right) {
+                        if self.state[index] == PixelState::Empty {
+                            self.data[index] = color;
+                        }
+                    }
+                }
+                self.state[sample] = tile_state[local];
+                self.computed += 1;
+            }
+        }
+    }
+
+    /// Store supersampled colors of edge pixels
+    fn store_refined(&mut self, refined: &[(Point32, [u8; 4])]) {
+        for &(point, color) in refined {
+            let index = (point.y as u32 * self.size.w + point.x as u32) as usize;
+            if matches!(self.state[index], PixelState::Done | PixelState::Limit) {
+                self.data[index] = color;
+                self.state[index] = PixelState::Refined;
+                self.computed += 1;
+            }
+        }
+    }
+
+    /// Queue the next finer pass of a finished tile, or count it as done after the finest pass
+    ///
+    /// A step of 0 denotes the final pass that supersamples edges.
+    fn complete_tile(&mut self, job: &Job) {
+        if job.step > 1 || (job.step == 1 && self.samples > 0) {
+            self.queue.push(job.tile, job.step / 2, job.generation);
+            self.wake.notify_one();
+        }
+        if job.step != 1 {
+            return;
+        }
+        self.pending -= 1;
+        if self.pending == 0 {
+            self.finish_pass();
+        }
+    }
+
+    /// Raise the iteration limit after a completed pass if the image has not yet stabilized
+    fn finish_pass(&mut self) {
+        const AUTO_ITER_THRESHOLD: f32 = 0.001;
+        const AUTO_ITER_MAX: u32 = 1 << 24;
+        if !self.auto_iter || self.max_iter >= AUTO_ITER_MAX {
+            return;
+        }
+        let unresolved = self
+            .state
+            .iter()
+            .filter(|&&state| state == PixelState::Limit)
+            .count() as u32;
+        // After raising, keep going only while the previous raise still resolved pixels
+        let changed = if self.refining {
+            self.resolved
+        } else {
+            unresolved
+        };
+        let threshold = ((self.size.w * self.size.h) as f32 * AUTO_ITER_THRESHOLD) as u32;
+        if unresolved > 0 && changed > threshold {
+            self.refine(self.max_iter.saturating_mul(2).min(AUTO_ITER_MAX));
+        }
+    }
+}
+
+/// Estimate the number of iterations needed at the given zoom depth
+///
+/// # Arguments
+/// * `depth` - Zoom depth in bits relative to the initial view
+///
+/// # Returns
+/// A starting iteration limit for automatic iteration selection
+fn auto_max_iter(depth: f32) -> u32 {
+    const AUTO_ITER_BASE: f32 = 256.0;
+    const AUTO_ITER_GROWTH: f32 = 64.0;
+    let ooms = depth.max(0.0) * 2.0_f32.log10();
+    (AUTO_ITER_BASE + AUTO_ITER_GROWTH * ooms.powf(1.5)) as u32
+}
+
+/// Values of the go-to dialog fields for a location, with empty iterations selecting them
+/// automatically
+fn goto_values(location: &Location, iterations: Option<u32>) -> Vec<String> {
+    vec![
+        format_coordinate(&location.real, location.depth),
+        format_coordinate(&location.imag, location.depth),
+        format_zoom(location.depth, 6),
+        iterations.map_or_else(String::new, |iterations| iterations.to_string()),
+    ]
+}
+
+/// Coloring parameters for escaped and interior pixels
+#[derive(Debug, Clone, Copy)]
+struct Palette {
+    color_cycle: u32,
+    saturation: f32,
+    interior_value: f32,
+}
+
+impl Palette {
+    fn color(&self, escape: Escape) -> (u8, u8, u8) {
+        match escape {
+            Escape::Escaped { iter, mag_sq } => {
+                let sub_iter = 4.5 / mag_sq - 0.125;
+                let hue = (iter as f32 + sub_iter).sqrt() / self.color_cycle as f32 * 360.0;
+                hsv_to_rgb(hue, self.saturation, 1.0)
+            }
+            Escape::Interior { period } => {
+                let hue = (period as f32).sqrt() / self.color_cycle as f32 * 360.0;
+                hsv_to_rgb(hue, self.saturation, self.interior_value)
+            }
+            Escape::Limit => (0, 0, 0),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct Rect {
     x: Float,
     y: Float,
     scale_exp: f32,
     scale_prec: u32,
+    /// Rotation of the buffer axes in degrees, clockwise with rows running down
+    angle: f32,
 }
 
 impl Rect {
     fn new(window_size: Size32, aliasing_factor: u32) -> Self {
-        const SCALE_PRECISION: u32 = 16;
         let mut rect = Self {
             x: Float::new(1),
             y: Float::new(1),
             scale_exp: 0.0,
-            scale_prec: SCALE_PRECISION,
+            scale_prec: 0,
+            angle: 0.0,
         };
+        rect.set_buffer_size(Size32 {
+            w: window_size.w * aliasing_factor,
+            h: window_size.h * aliasing_factor,
+        });
         let min_size = window_size.w.min(window_size.h);
         rect.scale_mul(1.0 / (min_size as f32 * aliasing_factor as f32));
         rect.offset_add(Point32 {
@@ -159,6 +616,37 @@ impl Rect {
         self.scale_exp as u32 + self.scale_prec
     }
 
+    /// Sets the bits beyond the zoom depth to those needed to address every pixel of a buffer,
+    /// plus a safety margin
+    fn set_buffer_size(&mut self, size: Size32) {
+        const PRECISION_MARGIN: u32 = 8;
+        self.scale_prec = size.w.max(size.h).max(1).ilog2() + 1 + PRECISION_MARGIN;
+        let prec = self.precision();
+        self.x.set_prec(prec);
+        self.y.set_prec(prec);
+    }
+
+    /// Whether neighboring pixels still map to distinct points in the arithmetic used to
+    /// iterate them
+    fn pixels_distinct(&self) -> bool {
+        let mantissa_bits = match self.arithmetic() {
+            Arithmetic::F64 => f64::MANTISSA_DIGITS,
+            Arithmetic::DoubleDouble => 2 * f64::MANTISSA_DIGITS,
+            Arithmetic::Mpfr => self.precision(),
+        } as i32;
+        // Iterated points are 3 * (pixel * scale + rect) - 0.5, so spacing is at least 2 * scale
+        // and magnitudes grow by up to 2 bits
+        let spacing_exp = 1 - self.scale_exp.ceil() as i32;
+        let magnitude_exp = [&self.x, &self.y]
+            .into_iter()
+            .filter_map(|value| value.get_exp())
+            .max()
+            .unwrap_or(0)
+            .max(0)
+            + 2;
+        spacing_exp >= magnitude_exp - mantissa_bits
+    }
+
     fn scale_mul(&mut self, factor: f32) {
         self.scale_exp -= factor.log2();
         let prec = self.precision();
@@ -172,16 +660,158 @@ impl Rect {
 
     fn offset_add(&mut self, delta: Point32) {
         let scale: Float = self.scale();
-        self.x += Float::with_val(self.x.prec(), delta.x) * &scale;
-        self.y += Float::with_val(self.y.prec(), delta.y) * &scale;
+        let (x, y) = self.rotate(delta.x as f64, delta.y as f64);
+        self.x += Float::with_val(self.x.prec(), x) * &scale;
+        self.y += Float::with_val(self.y.prec(), y) * &scale;
+    }
+
+    /// Sine and cosine of the view angle
+    fn rotation(&self) -> (f64, f64) {
+        (self.angle as f64).to_radians().sin_cos()
+    }
+
+    /// Turns an offset in buffer pixels by the view angle
+    fn rotate(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sin, cos) = self.rotation();
+        (cos * x - sin * y, sin * x + cos * y)
+    }
+
+    fn arithmetic(&self) -> Arithmetic {
+        // Room for the integer part of coordinates and the factor 3 in the mapping
+        const HEADROOM_BITS: f32 = 4.0;
+        let bits = self.scale_exp + HEADROOM_BITS;
+        if bits <= f64::MANTISSA_DIGITS as f32 {
+            Arithmetic::F64
+        } else if bits <= (2 * f64::MANTISSA_DIGITS) as f32 {
+            Arithmetic::DoubleDouble
+        } else {
+            Arithmetic::Mpfr
+        }
+    }
+
+    /// Binary exponent below which two orbit points are considered equal by periodicity checking
+    fn period_epsilon_exp(&self) -> i32 {
+        const PERIOD_MARGIN: i32 = 8;
+        -(self.scale_exp as i32) - PERIOD_MARGIN
+    }
+
+    /// View of a buffer of `size` pixels centered on a complex point
+    fn centered(real: &Float, imag: &Float, scale_exp: f32, angle: f32, size: Size32) -> Self {
+        let mut rect = Self {
+            x: Float::new(1),
+            y: Float::new(1),
+            scale_exp,
+            scale_prec: 0,
+            angle,
+        };
+        rect.set_buffer_size(size);
+        rect.set_center(real, imag, size);
+        rect
+    }
+
+    /// Complex point at the center of a buffer of `size` pixels
+    fn center(&self, size: Size32) -> (Float, Float) {
+        self.point(Point32 {
+            x: (size.w / 2) as i32,
+            y: (size.h / 2) as i32,
+        })
+    }
+
+    /// Complex point of a buffer pixel
+    fn point(&self, pixel: Point32) -> (Float, Float) {
+        let mut real = Float::new(self.precision());
+        let mut imag = Float::new(self.precision());
+        let scale = self.scale();
+        self.point_into(pixel.x as f64, pixel.y as f64, &scale, &mut real, &mut imag);
+        (real, imag)
+    }
+
+    /// Writes the complex point of a fractional buffer position into `real` and `imag`
+    fn point_into(&self, x: f64, y: f64, scale: &Float, real: &mut Float, imag: &mut Float) {
+        // c = 3 * (rotate(pixel) * scale + rect) - 0.5
+        let (x, y) = self.rotate(x, y);
+        real.assign(scale * x);
+        *real += &self.x;
+        *real *= 3;
+        *real -= 0.5;
+        imag.assign(scale * y);
+        *imag += &self.y;
+        *imag *= 3;
+    }
+
+    /// Fractional buffer position of a complex point, the inverse of `point_into`
+    fn pixel_of(&self, real: &Float, imag: &Float) -> (f64, f64) {
+        // p = unrotate(((c + 0.5) / 3 - rect) / scale)
+        let scale = self.scale();
+        let to_pixel = |value: &Float, offset: f64, origin: &Float| {
+            let mut position = Float::with_val(self.precision().max(value.prec()), value + offset);
+            position /= 3;
+            position -= origin;
+            position /= &scale;
+            position.to_f64()
+        };
+        let x = to_pixel(real, 0.5, &self.x);
+        let y = to_pixel(imag, 0.0, &self.y);
+        let (sin, cos) = self.rotation();
+        (cos * x + sin * y, cos * y - sin * x)
+    }
+
+    /// Moves the view so a complex point is at the center of a buffer of `size` pixels
+    fn set_center(&mut self, real: &Float, imag: &Float, size: Size32) {
+        self.set_point(
+            real,
+            imag,
+            Point32 {
+                x: (size.w / 2) as i32,
+                y: (size.h / 2) as i32,
+            },
+        );
+    }
+
+    /// Moves the view so a complex point is at a buffer pixel
+    fn set_point(&mut self, real: &Float, imag: &Float, pixel: Point32) {
+        let scale = self.scale();
+        let (x, y) = self.rotate(pixel.x as f64, pixel.y as f64);
+        self.x.assign(real + 0.5);
+        self.x /= 3;
+        self.x -= Float::with_val(self.precision(), &scale * x);
+        self.y.assign(imag / 3);
+        self.y -= Float::with_val(self.precision(), &scale * y);
     }
+}
+
+/// Number representation used for iterating, from fastest to most precise
+///
+/// There is no quad-double tier: past double-double, MPFR at a precision just above the depth
+/// is used, as quad-double would only bridge another 106 bits before it too runs out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Arithmetic {
+    F64,
+    DoubleDouble,
+    Mpfr,
+}
 
-    fn high_precision(&self) -> bool {
-        const F64_BITS: u32 = 52;
-        self.scale_exp > F64_BITS as f32
+impl Arithmetic {
+    fn name(&self) -> &'static str {
+        match self {
+            Arithmetic::F64 => "f64",
+            Arithmetic::DoubleDouble => "dd",
+            Arithmetic::Mpfr => "MPFR",
+        }
     }
 }
 
+/// Outcome of iterating a single point
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Escape {
+    /// The orbit escaped after `iter` iterations with squared magnitude `mag_sq`
+    Escaped { iter: u32, mag_sq: f32 },
+    /// The orbit was found to be attracted to a cycle of length `period`
+    Interior { period: u32 },
+    /// The iteration limit was reached without escape or detected period
+    Limit,
+}
+
 struct Canvas {
     offset: FPoint,
     scale: f32,
@@ -195,24 +825,85 @@ struct Input {
     mouse_moving: bool,
     mouse_scroll: f32,
     mouse_scrolling: bool,
+    /// Rotation in degrees still to be applied, gathered from keys and gestures
+    rotation: f32,
+    /// Left stick for panning and right stick for zooming, from -1 to 1 outside the dead zone
+    stick: FPoint,
+    stick_zoom: f32,
+    /// Whether the left and right triggers are pressed
+    triggers: [bool; 2],
     mouse_down: bool,
     shift_down: bool,
+    /// Corners of the rectangle being dragged out to zoom into, in window pixels
+    selection: Option<(Point32, Point32)>,
+    zoom_target: Option<ZoomTarget>,
+}
+
+/// Remaining zoom towards a selected rectangle
+struct ZoomTarget {
+    /// Window point that stays in place, where the selection is fitted to the window
+    anchor: FPoint,
+    /// Magnification still to be applied
+    remaining: f32,
 }
 
 impl App {
     fn new(config: Config) -> Self {
+        let size = config.buffer_size();
+        let mut buffer = Buffer {
+            size,
+            data: vec![[0; 4]; (size.w * size.h) as usize],
+            state: vec![PixelState::Empty; (size.w * size.h) as usize],
+            rect: Rect::new(config.window_size, config.aliasing_factor),
+            queue: TileQueue::new(),
+            generation: Arc::new(AtomicU64::new(0)),
+            wake: Arc::new(Condvar::new()),
+            idle: Arc::new(Condvar::new()),
+            focus: Point32 { x: 0, y: 0 },
+            pending: 0,
+            resolved: 0,
+            computed: 0,
+            active: 0,
+            julia_request: None,
+            julia_image: None,
+            julia_id: 0,
+            orbit_request: None,
+            orbit_trace: None,
+            orbit_id: 0,
+            minimap_requests: Vec::new(),
+            minimap_images: Vec::new(),
+            refining: false,
+            max_iter: if config.auto_iter {
+                auto_max_iter(0.0)
+            } else {
+                config.max_iter
+            },
+            auto_iter: config.auto_iter,
+            samples: config.adaptive_samples,
+            retitle: false,
+            flush: false,
+            stored: false,
+            exit: false,
+        };
+        buffer.restart(Point32 {
+            x: size.w as i32 / 2,
+            y: size.h as i32 / 2,
+        });
         Self {
             config,
             update_title: true,
-            buffer: Arc::new(Mutex::new(Buffer {
-                size: config.buffer_size(),
-                data: vec![0; config.buffer_length() as usize],
-                rect: Rect::new(config.window_size, config.aliasing_factor),
-                progress: 0,
-                max_iter: config.max_iter,
-                flush: false,
-                exit: false,
-            })),
+            pixels_distinct: true,
+            history: History::new(100),
+            navigating: false,
+            dialog: None,
+            hud: Hud::new(),
+            minimap: Minimap::new(),
+            orbit: None,
+            julia: None,
+            controllers: Vec::new(),
+            recorder: None,
+            replay: None,
+            buffer: Arc::new(Mutex::new(buffer)),
             canvas: Canvas {
                 offset: FPoint::new(0.0, 0.0),
                 scale: 1.0,
@@ -225,23 +916,55 @@ impl App {
                 mouse_moving: false,
                 mouse_scroll: 0.0,
                 mouse_scrolling: false,
+                rotation: 0.0,
+                stick: FPoint::new(0.0, 0.0),
+                stick_zoom: 0.0,
+                triggers: [false; 2],
                 mouse_down: false,
                 shift_down: false,
+                selection: None,
+                zoom_target: None,
             },
         }
     }
 
+    /// Whether neighboring pixels of the view are distinct, warning once when they stop being
+    fn check_pixels_distinct(&mut self) -> bool {
+        let buffer = self.buffer.lock().unwrap();
+        let pixels_distinct = buffer.rect.pixels_distinct();
+        if !pixels_distinct && self.pixels_distinct {
+            eprintln!(
+                "Pixel spacing is below {} precision, expect blocky artefacts",
+                buffer.rect.arithmetic().name()
+            );
+        }
+        drop(buffer);
+        self.pixels_distinct = pixels_distinct;
+        pixels_distinct
+    }
+
     fn update_window_title(&mut self, window: &mut sdl2::video::Window) {
         let buffer = self.buffer.lock().unwrap();
-        let min_size: u32 = self.config.window_size.w.min(self.config.window_size.h);
-        let offset = (min_size as f32 * self.config.aliasing_factor as f32).log2();
+        let offset = self.config.scale_offset();
         let ooms = (buffer.rect.scale_exp - offset) * (2.0 as f32).log10();
-        let precision = if buffer.rect.high_precision() {
-            "MPFR"
-        } else {
-            "f64"
-        };
-        let title = format!("Fractal - 10^{:.0} - {}", ooms, precision);
+        let precision = buffer.rect.arithmetic().name();
+        let iterations = if buffer.auto_iter { "auto" } else { "manual" };
+        let mut title = format!(
+            "Fractal - 10^{:.0} - {} - {} iter ({})",
+            ooms, precision, buffer.max_iter, iterations
+        );
+        if self.config.aliasing_factor > 1 {
+            title += &format!(" - {}", self.config.downsample_filter.name());
+        }
+        if self.recorder.is_some() {
+            title += " - recording";
+        } else if self.replay.is_some() {
+            title += " - replay";
+        }
+        drop(buffer);
+        if !self.check_pixels_distinct() {
+            title += " - pixels indistinct";
+        }
         window.set_title(&title).unwrap_or_else(|e| {
             eprintln!("Failed to update window title: {}", e);
         });
@@ -268,6 +991,16 @@ impl App {
 
         let texture_creator = canvas.texture_creator();
         let mut texture = self.create_texture(&texture_creator)?;
+        let mut julia_texture = texture_creator
+            .create_texture_streaming(PIXEL_FORMAT, JULIA_SIZE, JULIA_SIZE)
+            .map_err(|e| AppError::SdlError(e.to_string()))?;
+        let mut minimap_texture = texture_creator
+            .create_texture_streaming(
+                PIXEL_FORMAT,
+                MINIMAP_SIZE * (NESTED_LEVELS + 1),
+                MINIMAP_SIZE,
+            )
+            .map_err(|e| AppError::SdlError(e.to_string()))?;
 
         let mut event_pump = sdl_context
             .event_pump()
@@ -275,7 +1008,32 @@ impl App {
 
         let workers = self.start_workers();
 
-        while self.handle_events(&mut event_pump) {
+        const SESSION_INTERVAL: Duration = Duration::from_secs(1);
+        let mut saved_session = String::new();
+        let mut last_save = Instant::now();
+
+        // Newly computed pixels are shown at most this often, as each update downsamples the
+        // whole buffer
+        const UPLOAD_INTERVAL: Duration = Duration::from_millis(50);
+        let mut last_upload = Instant::now();
+        let mut frame = Vec::new();
+
+        let location = self.location();
+        let max_iter = self.buffer.lock().unwrap().max_iter;
+        self.history.push(HistoryEntry {
+            location,
+            max_iter,
+            snapshot: None,
+        });
+
+        let clipboard = video_subsystem.clipboard();
+        // Controllers are optional, so the explorer still runs without their subsystem
+        let controller_subsystem = sdl_context
+            .game_controller()
+            .inspect_err(|e| eprintln!("Failed to initialize game controllers: {}", e))
+            .ok();
+
+        while self.handle_events(&mut event_pump, &clipboard, controller_subsystem.as_ref()) {
             let frame_start = Instant::now();
 
             // Check if texture needs to be recreated after a resize
@@ -285,6 +1043,14 @@ impl App {
                 self.canvas.recreate = false;
             }
 
+            // Steer with the controller sticks, leaving the usual inertia once released
+            self.steer();
+
+            // Before this frame's input moves the view, so a snapshot taken when navigation
+            // starts matches the view left
+            self.track_history();
+            self.play();
+
             // Pan on mouse down
             if self.input.mouse_moving
                 || self.input.mouse_movement.x.abs() > 0.5
@@ -305,6 +1071,20 @@ impl App {
                 self.canvas.flush = true;
             }
 
+            // Rotate once per frame, as each rotation recomputes the whole buffer, and not for
+            // the slight twist of fingers that pinch or pan
+            if self.input.rotation.abs() >= 1.0 {
+                self.rotate(self.input.rotation);
+                self.input.rotation = 0.0;
+                self.canvas.flush = true;
+            }
+
+            // Zoom towards a selected rectangle
+            if self.input.zoom_target.is_some() {
+                self.zoom_step();
+                self.canvas.flush = true;
+            }
+
             // Scale up or down when scale out of bounds
             if self.canvas.scale > 2.2 {
                 self.scale(ScaleDirection::Up)
@@ -326,12 +1106,64 @@ impl App {
             // Update texture
             {
                 let mut buffer = self.buffer.lock().unwrap();
-                if buffer.flush {
+                if buffer.retitle {
+                    self.update_title = true;
+                    buffer.retitle = false;
+                }
+                if buffer.flush || (buffer.stored && last_upload.elapsed() >= UPLOAD_INTERVAL) {
+                    buffer.flush = false;
+                    buffer.stored = false;
+                    last_upload = Instant::now();
+                    // Read straight from the locked buffer, which needs no copy without
+                    // supersampling
+                    let pixels = if self.config.aliasing_factor == 1 {
+                        buffer.data.as_flattened()
+                    } else {
+                        downsample_into(
+                            &buffer.data,
+                            self.config.buffer_size(),
+                            self.config.aliasing_factor,
+                            self.config.downsample_filter,
+                            &mut frame,
+                        );
+                        frame.as_flattened()
+                    };
                     texture
-                        .update(None, &buffer.data, self.config.buffer_pitch() as usize)
+                        .update(None, pixels, self.config.window_pitch() as usize)
                         .map_err(|e| AppError::SdlError(e.to_string()))?;
+                    drop(buffer);
+                    self.canvas.flush = true;
+                }
+            }
+
+            // Follow the view on the minimap, computing levels on idle workers
+            let minimap_location = self.minimap.visible.then(|| self.location());
+            if let Some(location) = &minimap_location {
+                let mut buffer = self.buffer.lock().unwrap();
+                let images = std::mem::take(&mut buffer.minimap_images);
+                let max_iter = buffer.max_iter;
+                if self
+                    .minimap
+                    .update(location, max_iter, &mut buffer.minimap_requests)
+                {
+                    buffer.wake.notify_all();
+                }
+                drop(buffer);
+                for image in images {
+                    self.minimap.store(image);
+                }
+                if self.minimap.upload(&mut minimap_texture)? {
+                    self.canvas.flush = true;
+                }
+            }
+
+            // Keep the statistics in the overlay current, redrawing only when they changed
+            if self.hud.visible && self.hud.due() {
+                let computed = self.buffer.lock().unwrap().computed;
+                self.hud.measure(computed);
+                let lines = self.hud_lines();
+                if self.hud.set_lines(lines) {
                     self.canvas.flush = true;
-                    buffer.flush = false;
                 }
             }
 
@@ -345,6 +1177,32 @@ impl App {
                     self.canvas.scale * self.config.window_size.h as f32,
                 );
                 canvas.copy_f(&texture, None, rect)?;
+                if let Some((start, end)) = self.input.selection {
+                    let color = canvas.draw_color();
+                    canvas.set_draw_color(Color::RGB(0xFF, 0xFF, 0xFF));
+                    canvas.draw_rect(sdl2::rect::Rect::new(
+                        start.x.min(end.x),
+                        start.y.min(end.y),
+                        start.x.abs_diff(end.x),
+                        start.y.abs_diff(end.y),
+                    ))?;
+                    canvas.set_draw_color(color);
+                }
+                if let Some(orbit) = &self.orbit {
+                    self.draw_orbit(&mut canvas, orbit)?;
+                }
+                if self.julia.is_some() {
+                    self.draw_julia(&mut canvas, &julia_texture)?;
+                }
+                if let Some(location) = &minimap_location {
+                    self.minimap.draw(&mut canvas, &minimap_texture, location)?;
+                }
+                if self.hud.visible {
+                    self.hud.draw(&mut canvas)?;
+                }
+                if let Some(dialog) = &self.dialog {
+                    dialog.draw(&mut canvas)?;
+                }
                 canvas.present();
                 self.canvas.flush = false;
             }
@@ -354,8 +1212,64 @@ impl App {
                 self.update_title = false;
             }
 
+            if self.recorder.is_some() {
+                let location = self.location();
+                let max_iter = self.buffer.lock().unwrap().max_iter;
+                if let Some(recorder) = &mut self.recorder
+                    && let Err(e) = recorder.record(&location, max_iter)
+                {
+                    eprintln!("Failed to record, stopping: {}", e);
+                    self.recorder = None;
+                    self.update_title = true;
+                }
+            }
+
+            // Save the session once navigation settles, so it survives crashes
+            if last_save.elapsed() > SESSION_INTERVAL && self.settled() {
+                let session = self.session();
+                if session != saved_session {
+                    session::save(&session).unwrap_or_else(|e| {
+                        eprintln!("Failed to save session: {}", e);
+                    });
+                    saved_session = session;
+                }
+                last_save = Instant::now();
+            }
+
+            self.request_orbit();
+            self.request_julia();
+
+            // Show the orbit once a worker traced it
+            if let Some(orbit) = &mut self.orbit {
+                let buffer = self.buffer.lock().unwrap();
+                if let Some(trace) = &buffer.orbit_trace
+                    && trace.id > orbit.shown
+                {
+                    orbit.points = trace.points.clone();
+                    orbit.escape = Some(trace.escape);
+                    orbit.max_iter = trace.max_iter;
+                    orbit.shown = trace.id;
+                    self.canvas.flush = true;
+                }
+            }
+
+            // Show the Julia preview once a worker computed it
+            if let Some(julia) = &mut self.julia {
+                let buffer = self.buffer.lock().unwrap();
+                if let Some(image) = &buffer.julia_image
+                    && image.id > julia.shown
+                {
+                    julia_texture
+                        .update(None, image.data.as_flattened(), JULIA_SIZE as usize * 4)
+                        .map_err(|e| AppError::SdlError(e.to_string()))?;
+                    julia.shown = image.id;
+                    self.canvas.flush = true;
+                }
+            }
+
             // Sleep if we're running too fast
             let frame_duration = frame_start.elapsed();
+            self.hud.frame_time = frame_duration;
             if frame_duration < self.config.target_frame_duration() {
                 thread::sleep(self.config.target_frame_duration() - frame_duration);
             }
@@ -363,6 +1277,10 @@ impl App {
 
         // Wait for all workers to finish
         self.join_workers(workers)?;
+        session::save(&self.session())?;
+        if let Some(recorder) = self.recorder.take() {
+            recorder.finish()?;
+        }
 
         Ok(())
     }
@@ -371,9 +1289,9 @@ impl App {
         &self,
         texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
     ) -> Result<sdl2::render::Texture<'a>, AppError> {
-        let size = self.config.buffer_size();
+        let size = self.config.window_size;
         let mut texture = texture_creator
-            .create_texture_streaming(None, size.w, size.h)
+            .create_texture_streaming(PIXEL_FORMAT, size.w, size.h)
             .map_err(|e| AppError::SdlError(e.to_string()))?;
         texture.set_blend_mode(BlendMode::Blend);
         set_scale_mode_best(&mut texture);
@@ -382,51 +1300,109 @@ impl App {
 
     fn start_workers(&mut self) -> Vec<thread::JoinHandle<()>> {
         let mut handles = Vec::with_capacity(self.config.worker_threads);
-        let color_cycle = self.config.color_cycle;
-        let saturation = self.config.saturation;
+        let palette = self.config.palette();
 
         for _ in 0..self.config.worker_threads {
             let buffer = Arc::clone(&self.buffer);
+            let (generation, wake) = {
+                let buffer = buffer.lock().unwrap();
+                (Arc::clone(&buffer.generation), Arc::clone(&buffer.wake))
+            };
             let handle = thread::spawn(move || {
+                let mut scratch = Scratch::new();
                 loop {
-                    let (progress, rect, size, max_iter) = {
-                        let mut buffer = buffer.lock().unwrap();
-                        if buffer.exit {
-                            break;
-                        }
-                        buffer.progress += 1;
-                        (
-                            buffer.progress - 1,
-                            buffer.rect.clone(),
-                            buffer.size,
-                            buffer.max_iter,
-                        )
+                    let (job, render, area, area_buffer, mut area_state) = {
+                        let mut guard = buffer.lock().unwrap();
+                        let job = loop {
+                            if guard.exit {
+                                return;
+                            }
+                            if let Some(job) = guard.queue.pop() {
+                                guard.active += 1;
+                                break job;
+                            }
+                            // The Julia preview only gets workers that have no tiles to compute
+                            if let Some(request) = guard.julia_request.take() {
+                                drop(guard);
+                                let image = julia::render(&request, &palette);
+                                guard = buffer.lock().unwrap();
+                                if guard
+                                    .julia_image
+                                    .as_ref()
+                                    .is_none_or(|shown| shown.id < image.id)
+                                {
+                                    guard.julia_image = Some(image);
+                                }
+                                continue;
+                            }
+                            if let Some(request) = guard.orbit_request.take() {
+                                drop(guard);
+                                let trace = request.trace(&mut scratch);
+                                guard = buffer.lock().unwrap();
+                                if guard
+                                    .orbit_trace
+                                    .as_ref()
+                                    .is_none_or(|shown| shown.id < trace.id)
+                                {
+                                    guard.orbit_trace = Some(trace);
+                                }
+                                continue;
+                            }
+                            if let Some(request) = guard.minimap_requests.pop() {
+                                drop(guard);
+                                let image = minimap::render(&request, &palette, &mut scratch);
+                                guard = buffer.lock().unwrap();
+                                guard.minimap_images.push(image);
+                                continue;
+                            }
+                            guard = wake.wait(guard).unwrap();
+                        };
+                        // Finding edges needs the pixels around the tile
+                        let (area, area_buffer) = if job.step == 0 {
+                            let area = job.tile.expand(1, guard.size);
+                            (area, area.copy_from(&guard.data, guard.size.w))
+                        } else {
+                            (job.tile, Vec::new())
+                        };
+                        let area_state = area.copy_from(&guard.state, guard.size.w);
+                        (job, guard.render(palette), area, area_buffer, area_state)
                     };
 
-                    if progress >= size.h {
-                        thread::sleep(Duration::from_millis(10));
+                    if job.step == 0 {
+                        let refined = App::refine_tile(
+                            &job,
+                            &render,
+                            &area,
+                            &area_buffer,
+                            &area_state,
+                            &generation,
+                            &mut scratch,
+                        );
+                        let mut buffer = buffer.lock().unwrap();
+                        buffer.active -= 1;
+                        if let Some(refined) = refined
+                            && generation.load(Ordering::SeqCst) == job.generation
+                        {
+                            buffer.store_refined(&refined);
+                            buffer.stored = true;
+                        }
+                        buffer.signal_idle();
                         continue;
                     }
 
-                    // interlace randomly
-                    let y = (progress * 31) % size.h;
-                    let row_buffer =
-                        App::fill_pixel_row(y, &rect, size.w, max_iter, color_cycle, saturation);
-
+                    let filled =
+                        App::fill_tile(&job, &render, &mut area_state, &generation, &mut scratch);
+                    let mut buffer = buffer.lock().unwrap();
+                    buffer.active -= 1;
+                    if let Some((tile_buffer, resolved)) = filled
+                        && generation.load(Ordering::SeqCst) == job.generation
                     {
-                        let mut buffer = buffer.lock().unwrap();
-                        if buffer.rect.scale_exp == rect.scale_exp
-                            && buffer.rect.x == rect.x
-                            && buffer.rect.y == rect.y
-                            && buffer.size.w == size.w
-                            && buffer.size.h == size.h
-                        {
-                            let buffer_index = (y * size.w * 4) as usize;
-                            buffer.data[buffer_index..buffer_index + size.w as usize * 4]
-                                .copy_from_slice(&row_buffer);
-                            buffer.flush = true;
-                        }
+                        buffer.store_tile(&job, &tile_buffer, &area_state);
+                        buffer.resolved += resolved;
+                        buffer.complete_tile(&job);
+                        buffer.stored = true;
                     }
+                    buffer.signal_idle();
                 }
             });
             handles.push(handle);
@@ -438,7 +1414,11 @@ impl App {
         &mut self,
         worker_handles: Vec<thread::JoinHandle<()>>,
     ) -> Result<(), AppError> {
-        self.buffer.lock().unwrap().exit = true;
+        {
+            let mut buffer = self.buffer.lock().unwrap();
+            buffer.exit = true;
+            buffer.wake.notify_all();
+        }
         for handle in worker_handles {
             handle.join().map_err(|e| {
                 AppError::IoError(std::io::Error::new(
@@ -450,127 +1430,256 @@ impl App {
         Ok(())
     }
 
-    fn fill_pixel_row(
-        y: u32,
-        rect: &Rect,
-        width: u32,
-        max_iter: u32,
-        color_cycle: u32,
-        saturation: f32,
-    ) -> Vec<u8> {
-        let mut row_buffer = vec![0; width as usize * 4];
-        let prec = rect.precision();
-        let scale = rect.scale();
-        for x in 0..width as usize {
-            let px = Float::with_val(prec, x) * &scale + &(rect.x);
-            let py = Float::with_val(prec, y) * &scale + &(rect.y);
-            let (r, g, b) = App::get_pixel_color(
-                px,
-                py,
-                max_iter,
-                rect.high_precision(),
-                color_cycle,
-                saturation,
-            );
-            let index = x * 4;
-            row_buffer[index] = r;
-            row_buffer[index + 1] = g;
-            row_buffer[index + 2] = b;
-            row_buffer[index + 3] = 0xFF;
-        }
-        row_buffer
-    }
-
-    fn get_pixel_color(
-        x: Float,
-        y: Float,
-        max_iter: u32,
-        high_precision: bool,
-        color_cycle: u32,
-        saturation: f32,
-    ) -> (u8, u8, u8) {
-        let mut c_real = x;
-        c_real *= 3.0;
-        c_real -= 0.5;
-        let mut c_imag = y;
-        c_imag *= 3.0;
-        let (iter, mag_sq) = if high_precision {
-            App::get_pixel_color_float(&c_real, &c_imag, max_iter)
-        } else {
-            App::get_pixel_color_f64(c_real.to_f64(), c_imag.to_f64(), max_iter)
-        };
-        if mag_sq < 4.0 {
-            return (0, 0, 0);
-        }
-        let sub_iter = 4.5 / mag_sq - 0.125;
-        let hue = (iter as f32 + sub_iter).sqrt() / color_cycle as f32 * 360.0;
-        return hsv_to_rgb(hue, saturation, 1.0);
-    }
-
-    fn get_pixel_color_f64(real: f64, imag: f64, max_iter: u32) -> (u32, f32) {
-        let c_real = real;
-        let c_imag = imag;
-        let mut z_real = 0.0;
-        let mut z_imag = 0.0;
-
-        for iter in 0..max_iter {
-            let real_sq = z_real * z_real;
-            let imag_sq = z_imag * z_imag;
-            let mag_sq = real_sq + imag_sq;
-
-            // Check if point escapes
-            if mag_sq > 4.0 {
-                return (iter, mag_sq as f32);
+    /// Compute the empty pixels of a tile at every `step`th row and column, returning the
+    /// tile colors and how many pixels escaped or were found interior
+    ///
+    /// Returns `None` as soon as the generation moves on from the job.
+    fn fill_tile(
+        job: &Job,
+        render: &Render,
+        tile_state: &mut [PixelState],
+        generation: &AtomicU64,
+        scratch: &mut Scratch,
+    ) -> Option<(Vec<[u8; 4]>, u32)> {
+        let width = job.tile.w as usize;
+        let mut tile_buffer = vec![[0; 4]; tile_state.len()];
+        let mut resolved = 0;
+        for (y, (row_buffer, row_state)) in tile_buffer
+            .chunks_mut(width)
+            .zip(tile_state.chunks_mut(width))
+            .enumerate()
+            .step_by(job.step as usize)
+        {
+            if generation.load(Ordering::Relaxed) != job.generation {
+                return None;
             }
-
-            // Apply the Mandelbrot iteration: z = z² + c
-            z_imag = 2.0 * z_real * z_imag + c_imag;
-            z_real = real_sq - imag_sq + c_real;
+            let origin = Point32 {
+                x: job.tile.x as i32,
+                y: (job.tile.y as usize + y) as i32,
+            };
+            resolved +=
+                App::fill_pixel_row(origin, job.step, render, row_buffer, row_state, scratch);
         }
-
-        // Point is in the Mandelbrot set (black)
-        return (0, 0.0);
+        Some((tile_buffer, resolved))
     }
 
-    fn get_pixel_color_float(real: &Float, imag: &Float, max_iter: u32) -> (u32, f32) {
-        let prec: u32 = real.prec();
-        let four = Float::with_val(prec, 4);
-        let c_real = real.clone();
-        let c_imag = imag.clone();
-        let mut z_real = Float::with_val(prec, 0);
-        let mut z_imag = Float::with_val(prec, 0);
-
-        for iter in 0..max_iter {
-            let mut real_sq = z_real.clone();
-            real_sq.square_mut();
-            let mut imag_sq = z_imag.clone();
-            imag_sq.square_mut();
-            let mut mag_sq = real_sq.clone();
-            mag_sq += &imag_sq;
-
-            // Check if point escapes
-            if mag_sq > four {
-                return (iter, mag_sq.to_f32());
+    /// Supersample the pixels of a tile that differ strongly from a neighbor, returning the
+    /// averaged color of each refined pixel
+    ///
+    /// The `area` around the tile provides the neighbors of its edge pixels. Returns `None` as
+    /// soon as the generation moves on from the job.
+    fn refine_tile(
+        job: &Job,
+        render: &Render,
+        area: &Tile,
+        area_buffer: &[[u8; 4]],
+        area_state: &[PixelState],
+        generation: &AtomicU64,
+        scratch: &mut Scratch,
+    ) -> Option<Vec<(Point32, [u8; 4])>> {
+        const ADAPTIVE_THRESHOLD: u32 = 32;
+        let rect = &render.rect;
+        let scale = rect.scale();
+        scratch.set_prec(rect.precision());
+        let width = area.w as usize;
+        let mut refined = Vec::new();
+        for y in job.tile.y..job.tile.y + job.tile.h {
+            if generation.load(Ordering::Relaxed) != job.generation {
+                return None;
             }
+            for x in job.tile.x..job.tile.x + job.tile.w {
+                let index = (y - area.y) as usize * width + (x - area.x) as usize;
+                if !matches!(area_state[index], PixelState::Done | PixelState::Limit) {
+                    continue;
+                }
+                let color = area_buffer[index];
+                let neighbors = [
+                    (x > area.x).then(|| index - 1),
+                    (x + 1 < area.x + area.w).then(|| index + 1),
+                    (y > area.y).then(|| index - width),
+                    (y + 1 < area.y + area.h).then(|| index + width),
+                ];
+                let contrast = neighbors
+                    .into_iter()
+                    .flatten()
+                    .filter(|&neighbor| area_state[neighbor] != PixelState::Empty)
+                    .map(|neighbor| color_distance(color, area_buffer[neighbor]))
+                    .max()
+                    .unwrap_or(0);
+                if contrast < ADAPTIVE_THRESHOLD {
+                    continue;
+                }
 
-            // Apply the Mandelbrot iteration: z = z² + c
-            // z_imag = 2.0 * z_real * z_imag + c_imag;
-            z_real <<= 1;
-            z_imag.mul_add_mut(&z_real, &c_imag);
-            // z_real = real_sq - imag_sq + c_real;
-            z_real = real_sq;
-            z_real -= &imag_sq;
-            z_real += &c_real;
+                // Stronger edges get more samples
+                let count =
+                    (render.samples * contrast / 128).clamp(render.samples.min(4), render.samples);
+                let mut sum = color.map(srgb_to_linear);
+                for sample in 1..=count {
+                    let (dx, dy) = subsample_offset(sample);
+                    let escape = iterate::get_pixel_escape(
+                        x as f64 + dx,
+                        y as f64 + dy,
+                        rect,
+                        &scale,
+                        render.max_iter,
+                        scratch,
+                    );
+                    let (r, g, b) = render.palette.color(escape);
+                    sum[0] += srgb_to_linear(r);
+                    sum[1] += srgb_to_linear(g);
+                    sum[2] += srgb_to_linear(b);
+                }
+                let average = sum.map(|channel| linear_to_srgb(channel / (count + 1) as f32));
+                refined.push((
+                    Point32 {
+                        x: x as i32,
+                        y: y as i32,
+                    },
+                    [average[0], average[1], average[2], 0xFF],
+                ));
+            }
         }
-
-        // Point is in the Mandelbrot set (black)
-        return (0, 0.0);
+        Some(refined)
     }
 
-    fn handle_events(&mut self, event_pump: &mut EventPump) -> bool {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
+    /// Compute the empty pixels of a row starting at `origin` at every `step`th column,
+    /// returning how many escaped or were found interior
+    fn fill_pixel_row(
+        origin: Point32,
+        step: u32,
+        render: &Render,
+        row_buffer: &mut [[u8; 4]],
+        row_state: &mut [PixelState],
+        scratch: &mut Scratch,
+    ) -> u32 {
+        let rect = &render.rect;
+        let scale = rect.scale();
+        let epsilon_exp = rect.period_epsilon_exp();
+
+        // Step along the row by 3 * scale per sample, turned by the view angle
+        scratch.set_prec(rect.precision());
+        rect.point_into(
+            origin.x as f64,
+            origin.y as f64,
+            &scale,
+            &mut scratch.c_real,
+            &mut scratch.c_imag,
+        );
+        let (step_real, step_imag) = rect.rotate(3.0 * step as f64, 0.0);
+        scratch.c_step_real.assign(&scale * step_real);
+        scratch.c_step_imag.assign(&scale * step_imag);
+
+        let columns: Vec<usize> = (0..row_state.len())
+            .step_by(step as usize)
+            .filter(|&x| row_state[x] == PixelState::Empty)
+            .collect();
+        let escapes: Vec<Escape> = match rect.arithmetic() {
+            Arithmetic::Mpfr => {
+                let mut escapes = Vec::with_capacity(columns.len());
+                let mut sample = 0;
+                for &x in &columns {
+                    while sample < x {
+                        scratch.c_real += &scratch.c_step_real;
+                        scratch.c_imag += &scratch.c_step_imag;
+                        sample += step as usize;
+                    }
+                    escapes.push(iterate::escape_mpfr(scratch, render.max_iter, epsilon_exp));
+                }
+                escapes
+            }
+            arithmetic => {
+                let c_real = DoubleDouble::from_float(&scratch.c_real);
+                let c_imag = DoubleDouble::from_float(&scratch.c_imag);
+                let c_step_real = DoubleDouble::from_float(&scratch.c_step_real);
+                let c_step_imag = DoubleDouble::from_float(&scratch.c_step_imag);
+                let epsilon = (epsilon_exp as f64).exp2();
+                let samples = columns.iter().map(|&x| {
+                    let sample = DoubleDouble::new((x / step as usize) as f64);
+                    (c_real + c_step_real * sample, c_imag + c_step_imag * sample)
+                });
+                if arithmetic == Arithmetic::F64 {
+                    let points: Vec<(f64, f64)> =
+                        samples.map(|(real, imag)| (real.hi, imag.hi)).collect();
+                    iterate::escapes_f64(&points, render.max_iter, epsilon)
+                } else {
+                    samples
+                        .map(|(real, imag)| {
+                            iterate::escape_dd(real, imag, render.max_iter, epsilon)
+                        })
+                        .collect()
+                }
+            }
+        };
+        let mut resolved = 0;
+        for (x, escape) in columns.into_iter().zip(escapes) {
+            let state = &mut row_state[x];
+            *state = if escape == Escape::Limit {
+                PixelState::Limit
+            } else {
+                resolved += 1;
+                PixelState::Done
+            };
+            let (r, g, b) = render.palette.color(escape);
+            row_buffer[x] = [r, g, b, 0xFF];
+        }
+        resolved
+    }
+
+    fn handle_events(
+        &mut self,
+        event_pump: &mut EventPump,
+        clipboard: &ClipboardUtil,
+        controller_subsystem: Option<&GameControllerSubsystem>,
+    ) -> bool {
+        // Degrees the view turns per key press
+        const ROTATE_STEP: f32 = 15.0;
+        // Pixels the view moves in the first frame after a key press
+        const PAN_STEP: f32 = 20.0;
+        // Stick deflection below which the stick counts as centered
+        const DEAD_ZONE: f32 = 0.2;
+        // Trigger pressure above which the trigger counts as pressed
+        const TRIGGER_THRESHOLD: f32 = 0.5;
+        // Scroll per change in finger distance, relative to the size of the touch device
+        const PINCH_SCROLL: f32 = 50.0;
+        for event in event_pump.poll_iter() {
+            // An open dialog takes all keyboard input, including Escape to close it
+            if let Some(dialog) = &mut self.dialog {
+                let action = match event {
+                    Event::Quit { .. } => return false,
+                    Event::KeyDown {
+                        keycode: Some(Keycode::V),
+                        keymod,
+                        ..
+                    } if keymod.intersects(
+                        Mod::LCTRLMOD | Mod::RCTRLMOD | Mod::LGUIMOD | Mod::RGUIMOD,
+                    ) =>
+                    {
+                        // A whole copied location fills every field, other text the focused one
+                        if let Ok(text) = clipboard.clipboard_text() {
+                            match parse_kfr(&text) {
+                                Ok((location, iterations)) => {
+                                    dialog.set_values(goto_values(&location, iterations))
+                                }
+                                Err(_) => dialog.insert(text.trim()),
+                            }
+                        }
+                        DialogAction::Edit
+                    }
+                    Event::KeyDown { .. } | Event::TextInput { .. } => dialog.handle(&event),
+                    _ => DialogAction::Edit,
+                };
+                match action {
+                    DialogAction::Edit => {}
+                    DialogAction::Cancel => self.dialog = None,
+                    DialogAction::Submit => self.submit_goto(),
+                }
+                self.canvas.flush = true;
+                if matches!(event, Event::KeyDown { .. } | Event::TextInput { .. }) {
+                    continue;
+                }
+            }
+            match event {
+                Event::Quit { .. }
                 | Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
@@ -608,43 +1717,224 @@ impl App {
                 } => {
                     self.input.mouse_scroll = -10.0;
                 }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Left | Keycode::A),
+                    ..
+                } => {
+                    self.input.mouse_movement.x = PAN_STEP;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Right | Keycode::D),
+                    ..
+                } => {
+                    self.input.mouse_movement.x = -PAN_STEP;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Up | Keycode::W),
+                    ..
+                } => {
+                    self.input.mouse_movement.y = PAN_STEP;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Down | Keycode::S),
+                    ..
+                } => {
+                    self.input.mouse_movement.y = -PAN_STEP;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Q),
+                    ..
+                } => {
+                    self.input.rotation += ROTATE_STEP;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::E),
+                    ..
+                } => {
+                    self.input.rotation -= ROTATE_STEP;
+                }
                 Event::KeyDown {
                     keycode: Some(Keycode::LeftBracket),
                     ..
                 } => {
-                    let mut buffer = self.buffer.lock().unwrap();
-                    buffer.max_iter = buffer.max_iter.saturating_sub(1000);
-                    buffer.progress = 0;
+                    self.change_max_iter(false);
                 }
                 Event::KeyDown {
                     keycode: Some(Keycode::RightBracket),
                     ..
+                } => {
+                    self.change_max_iter(true);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::I),
+                    ..
+                } => {
+                    let focus = self.focus();
+                    let mut buffer = self.buffer.lock().unwrap();
+                    buffer.auto_iter = !buffer.auto_iter;
+                    buffer.retitle = true;
+                    if buffer.auto_iter {
+                        let depth = buffer.rect.scale_exp - self.config.scale_offset();
+                        buffer.set_max_iter(auto_max_iter(depth));
+                        buffer.restart(focus);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F),
+                    ..
+                } => {
+                    self.config.downsample_filter = self.config.downsample_filter.next();
+                    self.buffer.lock().unwrap().flush = true;
+                    self.update_title = true;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::P),
+                    ..
+                } => {
+                    if let Err(e) = self.export() {
+                        eprintln!("Failed to export image: {}", e);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::Z),
+                    ..
+                } => {
+                    self.step_history(self.input.shift_down);
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::G),
+                    ..
+                } => {
+                    self.open_goto();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::C),
+                    ..
+                } => {
+                    let location = self.location();
+                    let max_iter = self.buffer.lock().unwrap().max_iter;
+                    clipboard
+                        .set_clipboard_text(&format_kfr(&location, max_iter))
+                        .unwrap_or_else(|e| {
+                            eprintln!("Failed to copy location: {}", e);
+                        });
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::V),
+                    ..
+                } => match clipboard
+                    .clipboard_text()
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| parse_kfr(&text))
+                {
+                    Ok((location, iterations)) => self.go_to(&location, iterations),
+                    Err(e) => eprintln!("Failed to paste location: {}", e),
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::O),
+                    ..
                 } => {
                     let mut buffer = self.buffer.lock().unwrap();
-                    buffer.max_iter = buffer.max_iter.saturating_add(1000);
-                    buffer.progress = 0;
+                    buffer.orbit_request = None;
+                    buffer.orbit_trace = None;
+                    self.orbit = match self.orbit {
+                        Some(_) => None,
+                        // Traces of earlier requests may still be in progress, so skip them
+                        None => Some(Orbit {
+                            pixel: self.focus(),
+                            generation: 0,
+                            requested: 0,
+                            shown: buffer.orbit_id,
+                            points: Vec::new(),
+                            escape: None,
+                            max_iter: 0,
+                        }),
+                    };
+                    drop(buffer);
+                    self.canvas.flush = true;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::J),
+                    ..
+                } => {
+                    let mut buffer = self.buffer.lock().unwrap();
+                    buffer.julia_request = None;
+                    buffer.julia_image = None;
+                    self.julia = match self.julia {
+                        Some(_) => None,
+                        // Images of earlier requests may still be in progress, so skip them
+                        None => Some(JuliaInset {
+                            pixel: self.focus(),
+                            generation: 0,
+                            requested: 0,
+                            shown: buffer.julia_id,
+                        }),
+                    };
+                    drop(buffer);
+                    self.canvas.flush = true;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::R),
+                    ..
+                } => {
+                    self.toggle_recording();
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::M),
+                    ..
+                } => {
+                    self.minimap.visible = !self.minimap.visible;
+                    self.canvas.flush = true;
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::H),
+                    ..
+                } => {
+                    self.hud.toggle();
+                    self.canvas.flush = true;
                 }
                 Event::MouseButtonDown {
                     x,
                     y,
-                    mouse_btn: MouseButton::Left,
-                    clicks,
+                    mouse_btn: MouseButton::Right,
                     ..
                 } => {
-                    self.input.mouse_down = true;
-                    self.input.mouse_position.x = x;
-                    self.input.mouse_position.y = y;
-                    if clicks == 2 {
-                        self.input.mouse_scroll = if self.input.shift_down { -10.0 } else { 10.0 };
-                    }
+                    self.input.selection = Some((Point32 { x, y }, Point32 { x, y }));
                 }
-                Event::MouseButtonUp {
+                Event::MouseButtonDown {
+                    x,
+                    y,
                     mouse_btn: MouseButton::Left,
+                    clicks,
                     ..
                 } => {
-                    self.input.mouse_down = false;
+                    self.input.zoom_target = None;
+                    if self.input.shift_down && clicks == 1 {
+                        self.input.selection = Some((Point32 { x, y }, Point32 { x, y }));
+                    } else {
+                        self.input.mouse_down = true;
+                        self.input.mouse_position.x = x;
+                        self.input.mouse_position.y = y;
+                        if clicks == 2 {
+                            self.input.mouse_scroll =
+                                if self.input.shift_down { -10.0 } else { 10.0 };
+                        }
+                    }
+                }
+                Event::MouseButtonUp { mouse_btn, .. } => {
+                    if mouse_btn == MouseButton::Left {
+                        self.input.mouse_down = false;
+                    }
+                    if let Some((start, end)) = self.input.selection.take() {
+                        self.zoom_to_selection(start, end);
+                        self.canvas.flush = true;
+                    }
                 }
                 Event::MouseMotion { x, y, .. } => {
+                    if let Some((_, end)) = &mut self.input.selection {
+                        *end = Point32 { x, y };
+                        self.canvas.flush = true;
+                    }
                     if self.input.mouse_down {
                         self.input.mouse_movement.x = (x - self.input.mouse_position.x) as f32;
                         self.input.mouse_movement.y = (y - self.input.mouse_position.y) as f32;
@@ -653,11 +1943,65 @@ impl App {
                     self.input.mouse_position.x = x;
                     self.input.mouse_position.y = y;
                 }
-                Event::MouseWheel {
-                    y: mouse_scroll, ..
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Some(controller_subsystem) = controller_subsystem {
+                        match controller_subsystem.open(which) {
+                            Ok(controller) => self.controllers.push(controller),
+                            Err(e) => eprintln!("Failed to open game controller: {}", e),
+                        }
+                    }
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    self.controllers
+                        .retain(|controller| controller.instance_id() != which);
+                }
+                Event::ControllerAxisMotion { axis, value, .. } => {
+                    let value = value as f32 / i16::MAX as f32;
+                    let stick = if value.abs() < DEAD_ZONE { 0.0 } else { value };
+                    match axis {
+                        Axis::LeftX => self.input.stick.x = stick,
+                        Axis::LeftY => self.input.stick.y = stick,
+                        Axis::RightY => self.input.stick_zoom = -stick,
+                        Axis::RightX => {}
+                        Axis::TriggerLeft | Axis::TriggerRight => {
+                            let index = (axis == Axis::TriggerRight) as usize;
+                            let pressed = value > TRIGGER_THRESHOLD;
+                            if pressed && !self.input.triggers[index] {
+                                self.change_max_iter(index == 1);
+                            }
+                            self.input.triggers[index] = pressed;
+                        }
+                    }
+                }
+                Event::MultiGesture {
+                    touch_id,
+                    d_theta,
+                    d_dist,
+                    x,
+                    y,
+                    ..
                 } => {
-                    self.input.mouse_scroll = mouse_scroll as f32;
-                    self.input.mouse_scrolling = true;
+                    // Zoom around the fingers on a touchscreen, where they are given relative to
+                    // the window, and around the pointer on a touchpad
+                    if is_touchscreen(touch_id) {
+                        self.input.mouse_position = Point32 {
+                            x: (x * self.config.window_size.w as f32) as i32,
+                            y: (y * self.config.window_size.h as f32) as i32,
+                        };
+                    }
+                    if d_dist != 0.0 {
+                        self.scroll(d_dist * PINCH_SCROLL);
+                    }
+                    self.input.rotation += d_theta.to_degrees();
+                }
+                Event::MouseWheel { y, precise_y, .. } => {
+                    // Touchpads scroll in fractions, which older SDL versions round to zero
+                    let scroll = if precise_y != 0.0 {
+                        precise_y
+                    } else {
+                        y as f32
+                    };
+                    self.scroll(scroll);
                 }
                 _ => {}
             }
@@ -665,6 +2009,158 @@ impl App {
         true
     }
 
+    /// Save the current view as a bitmap in the working directory
+    fn export(&self) -> Result<(), AppError> {
+        let path = unused_path("bmp");
+        self.save_bmp(&path)?;
+        eprintln!("Exported {}", path.display());
+        Ok(())
+    }
+
+    /// Save the current view as a bitmap
+    fn save_bmp(&self, path: &Path) -> Result<(), AppError> {
+        let data = self.buffer.lock().unwrap().data.clone();
+        let size = self.config.window_size;
+        let mut frame = downsample(
+            &data,
+            self.config.buffer_size(),
+            self.config.aliasing_factor,
+            self.config.downsample_filter,
+        );
+        let surface = Surface::from_data(
+            frame.as_flattened_mut(),
+            size.w,
+            size.h,
+            self.config.window_pitch(),
+            PIXEL_FORMAT,
+        )?;
+        surface.save_bmp(path)?;
+        Ok(())
+    }
+
+    /// Start recording the views shown to a file in the working directory, or stop
+    fn toggle_recording(&mut self) {
+        self.update_title = true;
+        if let Some(recorder) = self.recorder.take() {
+            match recorder.finish() {
+                Ok(()) => eprintln!("Recording stopped"),
+                Err(e) => eprintln!("Failed to finish recording: {}", e),
+            }
+            return;
+        }
+        let path = unused_path("rec");
+        match Recorder::create(&path) {
+            Ok(recorder) => {
+                eprintln!("Recording to {}", path.display());
+                self.recorder = Some(recorder);
+            }
+            Err(e) => eprintln!("Failed to start recording: {}", e),
+        }
+    }
+
+    /// Show the frame of a replay that is due, handing control back after the last one
+    fn play(&mut self) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        let start = *replay.start.get_or_insert_with(Instant::now);
+        let time = start.elapsed().as_secs_f32();
+        let index = frame_at(&replay.frames, time);
+        let last = replay.frames.len() - 1;
+        let auto_iter = replay.auto_iter;
+        if replay.shown != Some(index) {
+            replay.shown = Some(index);
+            let frame = &replay.frames[index];
+            let (location, max_iter) = (frame.location.clone(), frame.max_iter);
+            self.show(&location, max_iter);
+        }
+        if index == last {
+            eprintln!("Replay finished");
+            self.replay = None;
+            self.buffer.lock().unwrap().auto_iter = auto_iter;
+            self.update_title = true;
+        }
+        // The recording already includes the inertia of its inputs
+        self.input.mouse_movement = FPoint::new(0.0, 0.0);
+        self.input.mouse_scroll = 0.0;
+        self.input.zoom_target = None;
+    }
+
+    /// Move the canvas so the view matches a location, reusing the buffer like interactive
+    /// navigation does, or jump there when the buffer is too far off
+    fn show(&mut self, location: &Location, max_iter: u32) {
+        // Canvas zoom beyond which the buffer is too coarse or too small to keep
+        const MAX_CANVAS_ZOOM: f32 = 16.0;
+        let angle = self.buffer.lock().unwrap().rect.angle;
+        if angle != location.angle {
+            self.rotate(location.angle - angle);
+        }
+
+        let focus = self.focus();
+        let size = self.config.buffer_size();
+        let mut buffer = self.buffer.lock().unwrap();
+        if max_iter > buffer.max_iter {
+            buffer.refine(max_iter);
+        } else if max_iter < buffer.max_iter {
+            buffer.set_max_iter(max_iter);
+            buffer.retitle = true;
+            buffer.restart(focus);
+        }
+        let scale = (location.depth + self.config.scale_offset() - buffer.rect.scale_exp).exp2();
+        let (x, y) = buffer.rect.pixel_of(&location.real, &location.imag);
+        drop(buffer);
+
+        let near = x.abs() < 2.0 * size.w as f64 && y.abs() < 2.0 * size.h as f64;
+        if !near || !(1.0 / MAX_CANVAS_ZOOM..=MAX_CANVAS_ZOOM).contains(&scale) {
+            self.jump(location, max_iter);
+            return;
+        }
+        let factor = scale / self.config.aliasing_factor as f32;
+        self.canvas.scale = scale;
+        self.canvas.offset = FPoint::new(
+            self.config.window_size.w as f32 / 2.0 - x as f32 * factor,
+            self.config.window_size.h as f32 / 2.0 - y as f32 * factor,
+        );
+        self.canvas.flush = true;
+        self.update_title = true;
+    }
+
+    /// Render a recording without a window, saving a bitmap per frame at the target frame rate
+    ///
+    /// Every frame is computed in full before it is saved, so the output does not depend on
+    /// the speed of the machine.
+    fn render_recording(&mut self, frames: &[Frame], dir: &Path) -> Result<(), AppError> {
+        fs::create_dir_all(dir)?;
+        let workers = self.start_workers();
+        // Use the recorded limits, as raising them depends on what the recording computed
+        let auto_iter = std::mem::replace(&mut self.buffer.lock().unwrap().auto_iter, false);
+        let fps = self.config.target_fps;
+        let count = (frames[frames.len() - 1].time * fps) as usize + 1;
+        let mut shown = None;
+        for index in 0..count {
+            let frame = frame_at(frames, index as f32 / fps);
+            if shown != Some(frame) {
+                self.jump(&frames[frame].location, frames[frame].max_iter);
+                self.check_pixels_distinct();
+                self.wait_idle();
+                shown = Some(frame);
+            }
+            self.save_bmp(&dir.join(format!("frame-{:05}.bmp", index)))?;
+        }
+        eprintln!("Rendered {} frames to {}", count, dir.display());
+        self.buffer.lock().unwrap().auto_iter = auto_iter;
+        self.join_workers(workers)
+    }
+
+    /// Block until the workers have finished all passes over the buffer
+    fn wait_idle(&self) {
+        let mut buffer = self.buffer.lock().unwrap();
+        let idle = Arc::clone(&buffer.idle);
+        while buffer.active > 0 || !buffer.queue.is_empty() {
+            buffer = idle.wait(buffer).unwrap();
+        }
+    }
+
     fn zoom(&mut self, multiplier: f32) {
         let zoom = multiplier * self.config.zoom_factor;
         // Adjust offset to keep mouse position stable
@@ -686,21 +2182,155 @@ impl App {
         self.canvas.scale *= 1.0 + zoom;
     }
 
+    /// Lower or raise the iteration limit by a fixed step, turning off automatic selection
+    fn change_max_iter(&mut self, increase: bool) {
+        let focus = self.focus();
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.auto_iter = false;
+        if increase {
+            let max_iter = buffer.max_iter.saturating_add(1000);
+            buffer.refine(max_iter);
+        } else {
+            let max_iter = buffer.max_iter.saturating_sub(1000);
+            buffer.set_max_iter(max_iter);
+            buffer.retitle = true;
+            buffer.restart(focus);
+        }
+    }
+
+    /// Pan and zoom at a speed set by the controller sticks, around the window center
+    fn steer(&mut self) {
+        // Pixels per frame at full deflection of the left stick
+        const PAN_SPEED: f32 = 20.0;
+        // Scroll per frame at full deflection of the right stick
+        const ZOOM_SPEED: f32 = 5.0;
+        let stick = self.input.stick;
+        if stick.x == 0.0 && stick.y == 0.0 && self.input.stick_zoom == 0.0 {
+            return;
+        }
+        // Without a pointer, zoom and render outward from the middle of the window
+        self.input.mouse_position = Point32 {
+            x: self.config.window_size.w as i32 / 2,
+            y: self.config.window_size.h as i32 / 2,
+        };
+        if stick.x != 0.0 || stick.y != 0.0 {
+            self.input.mouse_movement = FPoint::new(-stick.x * PAN_SPEED, -stick.y * PAN_SPEED);
+        }
+        if self.input.stick_zoom != 0.0 {
+            self.input.mouse_scroll = self.input.stick_zoom * ZOOM_SPEED;
+        }
+    }
+
+    /// Turn the view around the middle of the window, keeping the turned pixels as a preview
+    fn rotate(&mut self, angle: f32) {
+        let focus = self.focus();
+        let size = self.config.buffer_size();
+        // Buffer pixel at the middle of the window, wherever the canvas is panned and zoomed
+        let factor = self.config.aliasing_factor as f32 / self.canvas.scale;
+        let pivot = Point32 {
+            x: ((self.config.window_size.w as f32 / 2.0 - self.canvas.offset.x) * factor).round()
+                as i32,
+            y: ((self.config.window_size.h as f32 / 2.0 - self.canvas.offset.y) * factor).round()
+                as i32,
+        };
+        let mut buffer = self.buffer.lock().unwrap();
+        let (real, imag) = buffer.rect.point(pivot);
+        buffer.rect.angle = (buffer.rect.angle + angle).rem_euclid(360.0);
+        buffer.rect.set_point(&real, &imag, pivot);
+        buffer.data = rotate_rect(&buffer.data, size, size.w, pivot, angle);
+        // Turned pixels no longer sit at their computed positions
+        buffer.state.fill(PixelState::Empty);
+        buffer.restart(focus);
+        buffer.flush = true;
+    }
+
+    /// Add to the zoom speed, summing the many small deltas touchpads send within a frame
+    fn scroll(&mut self, amount: f32) {
+        if self.input.mouse_scrolling {
+            self.input.mouse_scroll += amount;
+        } else {
+            self.input.mouse_scroll = amount;
+        }
+        self.input.mouse_scrolling = true;
+        self.input.zoom_target = None;
+    }
+
+    /// Start zooming so a rectangle of the window fills it, keeping its aspect ratio
+    fn zoom_to_selection(&mut self, start: Point32, end: Point32) {
+        // Smaller selections are taken to be clicks
+        const MIN_SELECTION: f32 = 4.0;
+        let size = self.config.window_size;
+        let clamp = |point: Point32| {
+            FPoint::new(
+                point.x.clamp(0, size.w as i32) as f32,
+                point.y.clamp(0, size.h as i32) as f32,
+            )
+        };
+        let (start, end) = (clamp(start), clamp(end));
+        let width = (end.x - start.x).abs();
+        let height = (end.y - start.y).abs();
+        if width < MIN_SELECTION || height < MIN_SELECTION {
+            return;
+        }
+        let magnification = (size.w as f32 / width).min(size.h as f32 / height);
+        if magnification < 1.01 {
+            return;
+        }
+        // The point mapped onto itself when the selection center moves to the window center
+        let center = FPoint::new((start.x + end.x) / 2.0, (start.y + end.y) / 2.0);
+        let anchor = FPoint::new(
+            (size.w as f32 / 2.0 - magnification * center.x) / (1.0 - magnification),
+            (size.h as f32 / 2.0 - magnification * center.y) / (1.0 - magnification),
+        );
+        self.input.zoom_target = Some(ZoomTarget {
+            anchor,
+            remaining: magnification,
+        });
+    }
+
+    /// Apply part of the remaining zoom towards a selected rectangle
+    fn zoom_step(&mut self) {
+        let Some(target) = &mut self.input.zoom_target else {
+            return;
+        };
+        let factor = if target.remaining < 1.01 {
+            target.remaining
+        } else {
+            target.remaining.powf(1.0 - self.config.motion_decay)
+        };
+        self.canvas.offset.x = (self.canvas.offset.x - target.anchor.x) * factor + target.anchor.x;
+        self.canvas.offset.y = (self.canvas.offset.y - target.anchor.y) * factor + target.anchor.y;
+        self.canvas.scale *= factor;
+        target.remaining /= factor;
+        if target.remaining <= 1.0 {
+            self.input.zoom_target = None;
+        }
+    }
+
     fn scale(&mut self, direction: ScaleDirection) {
         // calculate the pixel offset as integer, so we snap to the nearest pixel
         let factor = match direction {
             ScaleDirection::Up => 0.5,
             ScaleDirection::Down => 2.0,
         };
+        // Scaling down moves by whole pixels of the halved buffer, so the delta must be even
+        let snap = match direction {
+            ScaleDirection::Up => 1,
+            ScaleDirection::Down => 2,
+        };
         let offset = Point32 {
             x: (((self.config.window_size.w as f32 - self.canvas.offset.x * 2.0)
                 / self.canvas.scale)
                 - self.config.window_size.w as f32 * factor) as i32
-                / 2,
+                / 2
+                / snap
+                * snap,
             y: (((self.config.window_size.h as f32 - self.canvas.offset.y * 2.0)
                 / self.canvas.scale)
                 - self.config.window_size.h as f32 * factor) as i32
-                / 2,
+                / 2
+                / snap
+                * snap,
         };
         let delta = Point32 {
             x: offset.x * self.config.aliasing_factor as i32,
@@ -711,18 +2341,22 @@ impl App {
         self.canvas.offset.y += offset.y as f32 * self.canvas.scale;
         self.canvas.scale *= factor;
 
+        let focus = self.focus();
+        let size = self.config.buffer_size();
         let mut buffer = self.buffer.lock().unwrap();
-        buffer.data = scale_rect(
-            &buffer.data,
-            self.config.buffer_size(),
-            self.config.buffer_pitch(),
-            delta,
-            direction,
-        );
+        buffer.data = scale_rect(&buffer.data, size, size.w, delta, direction);
+        buffer.state = scale_rect(&buffer.state, size, size.w, delta, direction);
+        buffer.rescale_state(direction);
 
         buffer.rect.offset_add(delta);
         buffer.rect.scale_mul(factor);
-        buffer.progress = 0;
+        if buffer.auto_iter {
+            // Only ever raise the limit here, so computed pixels stay valid when zooming out
+            let estimate = auto_max_iter(buffer.rect.scale_exp - self.config.scale_offset());
+            let max_iter = buffer.max_iter.max(estimate);
+            buffer.set_max_iter(max_iter);
+        }
+        buffer.restart(focus);
         buffer.flush = true;
         drop(buffer);
 
@@ -730,6 +2364,422 @@ impl App {
         self.update_title = true;
     }
 
+    /// Center and depth of the visible part of the buffer
+    fn location(&self) -> Location {
+        let aliasing_factor = self.config.aliasing_factor as f32;
+        let mut view = self.buffer.lock().unwrap().rect.clone();
+        view.offset_add(Point32 {
+            x: (-self.canvas.offset.x * aliasing_factor / self.canvas.scale).round() as i32,
+            y: (-self.canvas.offset.y * aliasing_factor / self.canvas.scale).round() as i32,
+        });
+        view.scale_mul(1.0 / self.canvas.scale);
+        let (real, imag) = view.center(self.config.buffer_size());
+        Location {
+            real,
+            imag,
+            depth: view.scale_exp - self.config.scale_offset(),
+            angle: view.angle,
+        }
+    }
+
+    /// Show a location, discarding the current buffer
+    fn jump(&mut self, location: &Location, max_iter: u32) {
+        let size = self.config.buffer_size();
+        let rect = Rect::centered(
+            &location.real,
+            &location.imag,
+            location.depth + self.config.scale_offset(),
+            location.angle,
+            size,
+        );
+        self.canvas.offset = FPoint::new(0.0, 0.0);
+        self.canvas.scale = 1.0;
+        self.input.mouse_movement = FPoint::new(0.0, 0.0);
+        self.input.mouse_scroll = 0.0;
+        self.input.zoom_target = None;
+
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.rect = rect;
+        buffer.state.fill(PixelState::Empty);
+        buffer.set_max_iter(max_iter);
+        buffer.restart(Point32 {
+            x: size.w as i32 / 2,
+            y: size.h as i32 / 2,
+        });
+        buffer.flush = true;
+        drop(buffer);
+
+        self.update_title = true;
+    }
+
+    /// Whether the view has come to rest after panning, zooming and rotating
+    fn settled(&self) -> bool {
+        !self.input.mouse_down
+            && self.input.mouse_movement.x.abs() <= 0.5
+            && self.input.mouse_movement.y.abs() <= 0.5
+            && self.input.mouse_scroll.abs() <= 0.5
+            && self.input.zoom_target.is_none()
+            && self.input.rotation.abs() < 1.0
+    }
+
+    /// Capture the buffer and canvas, to return to the view without recomputing
+    fn snapshot(&self) -> Snapshot {
+        let buffer = self.buffer.lock().unwrap();
+        Snapshot {
+            rect: buffer.rect.clone(),
+            size: buffer.size,
+            data: buffer.data.clone(),
+            state: buffer.state.clone(),
+            max_iter: buffer.max_iter,
+            offset: self.canvas.offset,
+            scale: self.canvas.scale,
+        }
+    }
+
+    /// Record the view in the history once navigation settles, and refresh the cached buffer
+    /// of the view being left when navigation starts
+    fn track_history(&mut self) {
+        let settled = self.settled();
+        if settled != self.navigating {
+            return;
+        }
+        self.navigating = !settled;
+        if !settled {
+            let snapshot = self.snapshot();
+            if let Some(entry) = self.history.current_mut() {
+                entry.snapshot = Some(snapshot);
+            }
+            return;
+        }
+
+        let location = self.location();
+        if let Some(entry) = self.history.current_mut()
+            && entry.location.real == location.real
+            && entry.location.imag == location.imag
+            && entry.location.depth == location.depth
+            && entry.location.angle == location.angle
+        {
+            return;
+        }
+        let max_iter = self.buffer.lock().unwrap().max_iter;
+        self.history.push(HistoryEntry {
+            location,
+            max_iter,
+            snapshot: None,
+        });
+        self.evict_snapshots();
+    }
+
+    /// Step back or forward through the history, showing the cached buffer when available
+    fn step_history(&mut self, forward: bool) {
+        let snapshot = self.snapshot();
+        if let Some(entry) = self.history.current_mut() {
+            entry.snapshot = Some(snapshot);
+        }
+        let entry = if forward {
+            self.history.forward()
+        } else {
+            self.history.back()
+        };
+        let Some(entry) = entry else {
+            return;
+        };
+        let location = entry.location.clone();
+        let max_iter = entry.max_iter;
+        let snapshot = entry.snapshot.take();
+        match snapshot {
+            Some(snapshot) if snapshot.size == self.config.buffer_size() => {
+                self.canvas.offset = snapshot.offset;
+                self.canvas.scale = snapshot.scale;
+                self.input.mouse_movement = FPoint::new(0.0, 0.0);
+                self.input.mouse_scroll = 0.0;
+                self.input.zoom_target = None;
+                let focus = self.focus();
+                let mut buffer = self.buffer.lock().unwrap();
+                buffer.rect = snapshot.rect;
+                buffer.data = snapshot.data;
+                buffer.state = snapshot.state;
+                buffer.max_iter = snapshot.max_iter;
+                buffer.restart(focus);
+                buffer.flush = true;
+                drop(buffer);
+                self.update_title = true;
+            }
+            _ => self.jump(&location, max_iter),
+        }
+        self.evict_snapshots();
+    }
+
+    /// Show a location as a new history entry, keeping the current buffer for going back
+    fn navigate_to(&mut self, location: &Location, max_iter: u32) {
+        let snapshot = self.snapshot();
+        if let Some(entry) = self.history.current_mut() {
+            entry.snapshot = Some(snapshot);
+        }
+        self.jump(location, max_iter);
+        self.history.push(HistoryEntry {
+            location: self.location(),
+            max_iter,
+            snapshot: None,
+        });
+        self.evict_snapshots();
+    }
+
+    /// Open the go-to dialog, filled in with the current location
+    fn open_goto(&mut self) {
+        let location = self.location();
+        let buffer = self.buffer.lock().unwrap();
+        let iterations = (!buffer.auto_iter).then_some(buffer.max_iter);
+        drop(buffer);
+        let labels = ["REAL", "IMAG", "ZOOM", "ITERATIONS"];
+        self.dialog = Some(Dialog::new(
+            "GO TO",
+            labels
+                .into_iter()
+                .zip(goto_values(&location, iterations))
+                .collect(),
+            Some('g'),
+        ));
+        self.canvas.flush = true;
+    }
+
+    /// Jump to the location entered in the go-to dialog, or show why it can't
+    ///
+    /// Leaving the iterations empty selects them automatically.
+    fn submit_goto(&mut self) {
+        let Some(dialog) = &mut self.dialog else {
+            return;
+        };
+        let values = dialog.values();
+        let angle = self.buffer.lock().unwrap().rect.angle;
+        let parsed = (|| {
+            let location = Location {
+                real: parse_float(values[0])?,
+                imag: parse_float(values[1])?,
+                depth: parse_zoom(values[2])?,
+                angle,
+            };
+            let iterations = match values[3].trim() {
+                "" => None,
+                value => Some(config::parse::<u32>(value)?.max(1)),
+            };
+            Ok::<_, String>((location, iterations))
+        })();
+        let (location, iterations) = match parsed {
+            Ok(parsed) => parsed,
+            Err(msg) => {
+                dialog.error = Some(msg);
+                return;
+            }
+        };
+        self.dialog = None;
+        self.go_to(&location, iterations);
+    }
+
+    /// Navigate to a location, selecting the iterations automatically when not given
+    fn go_to(&mut self, location: &Location, iterations: Option<u32>) {
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.auto_iter = iterations.is_none();
+        let max_iter = iterations.unwrap_or_else(|| auto_max_iter(location.depth));
+        drop(buffer);
+        self.navigate_to(location, max_iter);
+    }
+
+    /// Only keep cached buffers for entries near the current one
+    fn evict_snapshots(&mut self) {
+        const CACHED_ENTRIES: usize = 4;
+        let cursor = self.history.cursor();
+        for (index, entry) in self.history.iter_mut().enumerate() {
+            if index.abs_diff(cursor) > CACHED_ENTRIES {
+                entry.snapshot = None;
+            }
+        }
+    }
+
+    /// Text of the heads-up display
+    fn hud_lines(&self) -> Vec<String> {
+        let location = self.location();
+        let focus = self.focus();
+        let buffer = self.buffer.lock().unwrap();
+        let (mouse_real, mouse_imag) = buffer.rect.point(focus);
+        let computed = buffer
+            .state
+            .iter()
+            .filter(|&&state| state != PixelState::Empty)
+            .count();
+        let progress = computed as f64 / buffer.state.len().max(1) as f64 * 100.0;
+        let iterations = if buffer.auto_iter { "auto" } else { "manual" };
+        let speed = self.hud.pixels_per_second();
+        let mut lines = vec![
+            format!(
+                "RE    {}",
+                format_coordinate(&location.real, location.depth)
+            ),
+            format!(
+                "IM    {}",
+                format_coordinate(&location.imag, location.depth)
+            ),
+            format!("MOUSE {}", format_coordinate(&mouse_real, location.depth)),
+            format!("      {}", format_coordinate(&mouse_imag, location.depth)),
+            format!("ZOOM  {}", format_zoom(location.depth, 2)),
+            format!("ANGLE {:.1}", location.angle),
+            format!(
+                "PREC  {} bits ({})",
+                buffer.rect.precision(),
+                buffer.rect.arithmetic().name()
+            ),
+            format!("ITER  {} ({})", buffer.max_iter, iterations),
+            format!("DONE  {:.0}%", progress),
+            format!("SPEED {:.2}M pixels/s", speed / 1e6),
+            format!("FRAME {:.1} ms", self.hud.frame_time.as_secs_f64() * 1e3),
+        ];
+        if !buffer.rect.pixels_distinct() {
+            lines.push("WARN  pixels indistinct".to_string());
+        }
+        if let Some(orbit) = &self.orbit {
+            lines.push(match orbit.escape {
+                Some(Escape::Escaped { iter, .. }) => format!("ORBIT escapes at {}", iter),
+                Some(Escape::Interior { period }) => format!("ORBIT period {}", period),
+                Some(Escape::Limit) => format!("ORBIT no escape in {}", orbit.max_iter),
+                None => "ORBIT".to_string(),
+            });
+        }
+        lines
+    }
+
+    /// Ask an idle worker for the orbit of the point under the mouse, when it changed
+    fn request_orbit(&mut self) {
+        // Orbits hold up a worker that could be filling tiles, so limit their cost
+        const ORBIT_MAX_ITER: u32 = 100_000;
+
+        let pixel = self.focus();
+        let Some(orbit) = &mut self.orbit else {
+            return;
+        };
+        let mut buffer = self.buffer.lock().unwrap();
+        let generation = buffer.generation.load(Ordering::SeqCst);
+        if orbit.requested > 0 && orbit.pixel == pixel && orbit.generation == generation {
+            return;
+        }
+        orbit.pixel = pixel;
+        orbit.generation = generation;
+        buffer.orbit_id += 1;
+        orbit.requested = buffer.orbit_id;
+        buffer.orbit_request = Some(OrbitRequest {
+            rect: buffer.rect.clone(),
+            pixel,
+            max_iter: buffer.max_iter.min(ORBIT_MAX_ITER),
+            id: orbit.requested,
+        });
+        buffer.wake.notify_one();
+    }
+
+    /// Ask an idle worker for the Julia set of the point under the mouse, when it changed
+    fn request_julia(&mut self) {
+        let pixel = self.focus();
+        let Some(julia) = &mut self.julia else {
+            return;
+        };
+        let mut buffer = self.buffer.lock().unwrap();
+        let generation = buffer.generation.load(Ordering::SeqCst);
+        if julia.requested > 0 && julia.pixel == pixel && julia.generation == generation {
+            return;
+        }
+        let (c_real, c_imag) = buffer.rect.point(pixel);
+        julia.pixel = pixel;
+        julia.generation = generation;
+        buffer.julia_id += 1;
+        julia.requested = buffer.julia_id;
+        buffer.julia_request = Some(JuliaRequest {
+            c_real: c_real.to_f64(),
+            c_imag: c_imag.to_f64(),
+            max_iter: buffer.max_iter.min(JULIA_MAX_ITER),
+            id: julia.requested,
+        });
+        buffer.wake.notify_one();
+    }
+
+    /// Draw the Julia preview in the top-right corner
+    fn draw_julia(
+        &self,
+        canvas: &mut sdl2::render::WindowCanvas,
+        texture: &Texture,
+    ) -> Result<(), String> {
+        const MARGIN: i32 = 8;
+        let (width, _) = canvas.output_size()?;
+        let frame = sdl2::rect::Rect::new(
+            width as i32 - JULIA_SIZE as i32 - MARGIN,
+            MARGIN,
+            JULIA_SIZE,
+            JULIA_SIZE,
+        );
+        canvas.copy(texture, None, frame)?;
+        let color = canvas.draw_color();
+        canvas.set_draw_color(Color::RGB(0xFF, 0xFF, 0xFF));
+        canvas.draw_rect(frame)?;
+        canvas.set_draw_color(color);
+        Ok(())
+    }
+
+    /// Draw the orbit as connected points over the view
+    fn draw_orbit(
+        &self,
+        canvas: &mut sdl2::render::WindowCanvas,
+        orbit: &Orbit,
+    ) -> Result<(), String> {
+        const POINT_SIZE: f32 = 3.0;
+        let factor = self.canvas.scale / self.config.aliasing_factor as f32;
+        let points: Vec<FPoint> = orbit
+            .points
+            .iter()
+            .map(|point| {
+                FPoint::new(
+                    self.canvas.offset.x + (point.x + 0.5) * factor,
+                    self.canvas.offset.y + (point.y + 0.5) * factor,
+                )
+            })
+            .collect();
+        let squares: Vec<FRect> = points
+            .iter()
+            .map(|point| {
+                FRect::new(
+                    point.x - POINT_SIZE / 2.0,
+                    point.y - POINT_SIZE / 2.0,
+                    POINT_SIZE,
+                    POINT_SIZE,
+                )
+            })
+            .collect();
+
+        let color = canvas.draw_color();
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(0xFF, 0xFF, 0xFF, 0x80));
+        canvas.draw_flines(points.as_slice())?;
+        canvas.set_draw_color(Color::RGB(0xFF, 0xD0, 0x40));
+        canvas.fill_frects(&squares)?;
+        canvas.set_draw_color(color);
+        Ok(())
+    }
+
+    /// Serialized view and settings to restore on the next launch
+    fn session(&self) -> String {
+        let location = self.location();
+        let buffer = self.buffer.lock().unwrap();
+        session::format(&self.config, &location, buffer.max_iter, buffer.auto_iter)
+    }
+
+    /// Buffer pixel under the mouse, where rendering starts after the view changes
+    fn focus(&self) -> Point32 {
+        let size = self.config.window_size;
+        let factor = self.config.aliasing_factor as f32 / self.canvas.scale;
+        let x = self.input.mouse_position.x.clamp(0, size.w as i32) as f32;
+        let y = self.input.mouse_position.y.clamp(0, size.h as i32) as f32;
+        Point32 {
+            x: ((x - self.canvas.offset.x) * factor) as i32,
+            y: ((y - self.canvas.offset.y) * factor) as i32,
+        }
+    }
+
     fn pan(&mut self, movement: FPoint) {
         self.canvas.offset.x += movement.x;
         self.canvas.offset.y += movement.y;
@@ -752,47 +2802,115 @@ impl App {
         self.canvas.offset.y +=
             delta.y as f32 * self.canvas.scale / self.config.aliasing_factor as f32;
 
+        let focus = self.focus();
+        let size = self.config.buffer_size();
         let mut buffer = self.buffer.lock().unwrap();
-        buffer.data = translate_rect(
-            &buffer.data,
-            self.config.buffer_size(),
-            self.config.buffer_pitch(),
-            delta,
-        );
+        buffer.data = translate_rect(&buffer.data, size, size.w, delta);
+        buffer.state = translate_rect(&buffer.state, size, size.w, delta);
 
         buffer.rect.offset_add(delta);
-        buffer.progress = 0;
+        buffer.restart(focus);
         buffer.flush = true;
     }
 
     fn resize(&mut self, size: Size32) {
-        let buffer_size = self.config.buffer_size();
-        let buffer_pitch = self.config.buffer_pitch();
+        let src_size = self.config.buffer_size();
         self.config.window_size = size;
+        let dst_size = self.config.buffer_size();
 
+        let focus = self.focus();
         let mut buffer = self.buffer.lock().unwrap();
-        buffer.data = extend_buffer(
-            &buffer.data,
-            buffer_size,
-            buffer_pitch,
-            self.config.buffer_size(),
-            self.config.buffer_pitch(),
-        );
-        buffer.size = self.config.buffer_size();
+        buffer.data = extend_buffer(&buffer.data, src_size, src_size.w, dst_size, dst_size.w);
+        buffer.state = extend_buffer(&buffer.state, src_size, src_size.w, dst_size, dst_size.w);
+        buffer.size = dst_size;
+        buffer.rect.set_buffer_size(dst_size);
 
-        buffer.progress = 0;
+        buffer.restart(focus);
         buffer.flush = true;
+        self.update_title = true;
 
         self.canvas.recreate = true; // Signal texture recreation
     }
 }
 
 fn main() -> Result<(), AppError> {
-    let mut app = App::new(Config::default());
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print!("{}", config::USAGE);
+        return Ok(());
+    }
+    let fresh = args.iter().any(|arg| arg == "--fresh");
+    args.retain(|arg| arg != "--fresh");
+    let replay = config::take_flag(&mut args, "replay")?;
+    let render = config::take_flag(&mut args, "render")?;
+
+    // The config file and command-line arguments take precedence over the last session
+    let mut config = Config::default();
+    let restored = if fresh || replay.is_some() || render.is_some() {
+        None
+    } else {
+        session::load(&mut config).unwrap_or_else(|e| {
+            eprintln!("Failed to restore session: {}", e);
+            None
+        })
+    };
+    let config = config::load(&args, config)?;
+
+    if let Some(path) = render {
+        let path = Path::new(&path);
+        let frames = recording::load(path)?;
+        let mut app = App::new(config);
+        return app.render_recording(&frames, &path.with_extension("frames"));
+    }
+
+    let mut app = App::new(config);
+    if let Some(location) = restored {
+        app.jump(&location, config.max_iter);
+    }
+    if let Some(path) = replay {
+        let frames = recording::load(Path::new(&path))?;
+        app.jump(&frames[0].location, frames[0].max_iter);
+        let auto_iter = std::mem::replace(&mut app.buffer.lock().unwrap().auto_iter, false);
+        app.replay = Some(Replay {
+            frames,
+            start: None,
+            shown: Some(0),
+            auto_iter,
+        });
+    }
     app.run()?;
     Ok(())
 }
 
+/// Seconds since the Unix epoch, to name exported files
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+/// Path in the working directory named after the current time, numbered when files from the
+/// same second exist
+fn unused_path(extension: &str) -> PathBuf {
+    let stem = format!("fractal-{}", timestamp());
+    let mut path = PathBuf::from(format!("{stem}.{extension}"));
+    let mut number = 1;
+    while path.exists() {
+        number += 1;
+        path = PathBuf::from(format!("{stem}-{number}.{extension}"));
+    }
+    path
+}
+
+/// Whether a touch device is a touchscreen rather than a touchpad (to be replaced with rust
+/// binding when available)
+fn is_touchscreen(touch_id: i64) -> bool {
+    unsafe {
+        sys::SDL_GetTouchDeviceType(touch_id) == sys::SDL_TouchDeviceType::SDL_TOUCH_DEVICE_DIRECT
+    }
+}
+
 /// Set the blend mode of a texture (to be replaced with rust binding when available)
 fn set_scale_mode_best(texture: &mut Texture) {
     unsafe {
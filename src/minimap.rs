@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+
+use rug::Float;
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect as ScreenRect},
+    render::{BlendMode, Texture, WindowCanvas},
+};
+
+use crate::{
+    Palette, Rect, auto_max_iter,
+    iterate::{self, Scratch},
+    location::Location,
+    pixel::Size32,
+};
+
+/// Width and height of each minimap level in pixels
+pub const MINIMAP_SIZE: u32 = 96;
+/// Levels shown below the overview of the whole set
+pub const NESTED_LEVELS: u32 = 3;
+/// Distance between the levels and the window edges in screen pixels
+const MARGIN: i32 = 8;
+/// Smallest width and height of a drawn box in screen pixels
+const MIN_BOX: u32 = 3;
+
+/// A minimap level to be computed by a worker
+pub struct MinimapRequest {
+    pub level: u32,
+    /// Increasing number identifying the request, so outdated images can be skipped
+    pub id: u64,
+    pub rect: Rect,
+    pub max_iter: u32,
+}
+
+/// A computed minimap level
+pub struct MinimapImage {
+    pub level: u32,
+    pub id: u64,
+    pub data: Vec<[u8; 4]>,
+}
+
+/// A cached view of the set, one order of magnitude deeper than the level before
+struct Level {
+    id: u64,
+    rect: Rect,
+    real: Float,
+    imag: Float,
+    image: Option<Vec<[u8; 4]>>,
+}
+
+/// Overview of the whole set with nested views leading to the current one
+pub struct Minimap {
+    pub visible: bool,
+    levels: BTreeMap<u32, Level>,
+    /// Levels currently drawn, from the overview to the deepest
+    shown: Vec<u32>,
+    next_id: u64,
+    /// Whether the texture is behind the shown levels
+    dirty: bool,
+}
+
+/// Zoom depth in bits of a level
+fn level_depth(level: u32) -> f32 {
+    level as f32 * 10.0_f32.log2()
+}
+
+fn size() -> Size32 {
+    Size32 {
+        w: MINIMAP_SIZE,
+        h: MINIMAP_SIZE,
+    }
+}
+
+impl Level {
+    /// Position of a complex point in level pixels, relative to the level center
+    fn offset(&self, real: &Float, imag: &Float) -> (f64, f64) {
+        let scale = self.rect.scale() * 3;
+        let mut x = Float::with_val(real.prec(), real - &self.real);
+        x /= &scale;
+        let mut y = Float::with_val(imag.prec(), imag - &self.imag);
+        y /= &scale;
+        (x.to_f64(), y.to_f64())
+    }
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            levels: BTreeMap::new(),
+            shown: Vec::new(),
+            next_id: 0,
+            dirty: false,
+        }
+    }
+
+    /// Picks the levels leading to a location and requests those not cached yet
+    ///
+    /// The overview always shows the whole set. Deeper levels are centered on the location when
+    /// first requested and kept until the location leaves their middle half.
+    ///
+    /// # Arguments
+    /// * `location` - Current view
+    /// * `max_iter` - Iteration limit of the current view, capping that of the levels
+    /// * `requests` - Queue of levels waiting for a worker
+    ///
+    /// # Returns
+    /// Whether requests were added
+    pub fn update(
+        &mut self,
+        location: &Location,
+        max_iter: u32,
+        requests: &mut Vec<MinimapRequest>,
+    ) -> bool {
+        let deepest = (location.depth.max(0.0) / level_depth(1)) as u32;
+        let shown: Vec<u32> = std::iter::once(0)
+            .chain(deepest.saturating_sub(NESTED_LEVELS - 1).max(1)..=deepest)
+            .collect();
+
+        // Levels far below the view would need recentering before they are shown again
+        self.levels
+            .retain(|&level, _| level <= deepest + NESTED_LEVELS);
+
+        let mut requested = false;
+        for &level in &shown {
+            let cached = self.levels.get(&level).is_some_and(|cached| {
+                let (x, y) = cached.offset(&location.real, &location.imag);
+                let limit = MINIMAP_SIZE as f64 / 4.0;
+                level == 0 || (x.abs() <= limit && y.abs() <= limit)
+            });
+            if cached {
+                continue;
+            }
+            let (real, imag) = if level == 0 {
+                (Float::with_val(64, -0.5), Float::new(64))
+            } else {
+                (location.real.clone(), location.imag.clone())
+            };
+            let depth = level_depth(level);
+            let rect = Rect::centered(
+                &real,
+                &imag,
+                depth + (MINIMAP_SIZE as f32).log2(),
+                0.0,
+                size(),
+            );
+            self.next_id += 1;
+            requests.retain(|request| request.level != level);
+            requests.push(MinimapRequest {
+                level,
+                id: self.next_id,
+                rect: rect.clone(),
+                max_iter: auto_max_iter(depth).min(max_iter),
+            });
+            self.levels.insert(
+                level,
+                Level {
+                    id: self.next_id,
+                    rect,
+                    real,
+                    imag,
+                    image: None,
+                },
+            );
+            self.dirty = true;
+            requested = true;
+        }
+        if shown != self.shown {
+            self.shown = shown;
+            self.dirty = true;
+        }
+        requested
+    }
+
+    /// Caches a computed level, unless it was requested again since
+    pub fn store(&mut self, image: MinimapImage) {
+        if let Some(level) = self.levels.get_mut(&image.level)
+            && level.id == image.id
+        {
+            level.image = Some(image.data);
+            self.dirty = true;
+        }
+    }
+
+    /// Copies the shown levels into a texture with a slot for each, if they changed
+    ///
+    /// # Returns
+    /// Whether the texture was updated
+    pub fn upload(&mut self, texture: &mut Texture) -> Result<bool, String> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        let blank = vec![[0, 0, 0, 0xFF]; (MINIMAP_SIZE * MINIMAP_SIZE) as usize];
+        for (slot, level) in self.shown.iter().enumerate() {
+            let data = self.levels[level].image.as_ref().unwrap_or(&blank);
+            let target = ScreenRect::new(
+                (slot as u32 * MINIMAP_SIZE) as i32,
+                0,
+                MINIMAP_SIZE,
+                MINIMAP_SIZE,
+            );
+            texture
+                .update(target, data.as_flattened(), MINIMAP_SIZE as usize * 4)
+                .map_err(|e| e.to_string())?;
+        }
+        self.dirty = false;
+        Ok(true)
+    }
+
+    /// Draws the levels side by side in the bottom-right corner
+    ///
+    /// Each level marks the center of the location and outlines the next level, or the view
+    /// itself for the deepest one.
+    ///
+    /// # Arguments
+    /// * `canvas` - Canvas to draw on
+    /// * `texture` - Texture filled by `upload`
+    /// * `location` - Current view, which may be between levels while zooming
+    pub fn draw(
+        &self,
+        canvas: &mut WindowCanvas,
+        texture: &Texture,
+        location: &Location,
+    ) -> Result<(), String> {
+        let (width, height) = canvas.output_size()?;
+        let size = MINIMAP_SIZE as i32;
+        let left = width as i32 - MARGIN - self.shown.len() as i32 * (size + MARGIN) + MARGIN;
+        let top = height as i32 - MARGIN - size;
+        let min_size = width.min(height) as f32;
+
+        let color = canvas.draw_color();
+        canvas.set_blend_mode(BlendMode::Blend);
+        for (slot, level) in self.shown.iter().enumerate() {
+            let cached = &self.levels[level];
+            let frame = ScreenRect::new(
+                left + slot as i32 * (size + MARGIN),
+                top,
+                MINIMAP_SIZE,
+                MINIMAP_SIZE,
+            );
+            let source = ScreenRect::new(slot as i32 * size, 0, MINIMAP_SIZE, MINIMAP_SIZE);
+            canvas.copy(texture, source, frame)?;
+            canvas.set_draw_color(Color::RGB(0xFF, 0xFF, 0xFF));
+            canvas.draw_rect(frame)?;
+
+            // Box of the next level, or of the view past the deepest level, which is turned
+            // like the view as the levels are not
+            let depth = level_depth(*level);
+            let (center, span, angle) = match self.shown.get(slot + 1) {
+                Some(next) => {
+                    let span = (depth - level_depth(*next)).exp2() as f64;
+                    let next = &self.levels[next];
+                    (cached.offset(&next.real, &next.imag), (span, span), 0.0)
+                }
+                None => {
+                    let span = (depth - location.depth).exp2() as f64;
+                    (
+                        cached.offset(&location.real, &location.imag),
+                        (
+                            span * width as f64 / min_size as f64,
+                            span * height as f64 / min_size as f64,
+                        ),
+                        location.angle,
+                    )
+                }
+            };
+            let half_width = (span.0 * MINIMAP_SIZE as f64).max(MIN_BOX as f64) / 2.0;
+            let half_height = (span.1 * MINIMAP_SIZE as f64).max(MIN_BOX as f64) / 2.0;
+            let center_x = frame.x() as f64 + size as f64 / 2.0 + center.0;
+            let center_y = frame.y() as f64 + size as f64 / 2.0 + center.1;
+            let (sin, cos) = (angle as f64).to_radians().sin_cos();
+            let corners: Vec<Point> = [
+                (-1.0, -1.0),
+                (1.0, -1.0),
+                (1.0, 1.0),
+                (-1.0, 1.0),
+                (-1.0, -1.0),
+            ]
+            .into_iter()
+            .map(|(side_x, side_y)| {
+                let (x, y) = (side_x * half_width, side_y * half_height);
+                Point::new(
+                    (center_x + cos * x - sin * y).round() as i32,
+                    (center_y + sin * x + cos * y).round() as i32,
+                )
+            })
+            .collect();
+            canvas.set_clip_rect(frame);
+            canvas.set_draw_color(Color::RGBA(0xFF, 0xD0, 0x40, 0xC0));
+            canvas.draw_lines(corners.as_slice())?;
+
+            // Center of the view
+            let (x, y) = cached.offset(&location.real, &location.imag);
+            let marker_x = (frame.x() as f64 + size as f64 / 2.0 + x).round() as i32;
+            let marker_y = (frame.y() as f64 + size as f64 / 2.0 + y).round() as i32;
+            canvas.set_draw_color(Color::RGB(0xFF, 0x40, 0x40));
+            canvas.fill_rect(ScreenRect::from_center((marker_x, marker_y), 3, 3))?;
+            canvas.set_clip_rect(None);
+        }
+        canvas.set_draw_color(color);
+        Ok(())
+    }
+}
+
+/// Computes a minimap level
+///
+/// # Arguments
+/// * `request` - View and iteration limit of the level
+/// * `palette` - Colors of escaped and interior points, shared with the main view
+/// * `scratch` - Reusable numbers of the worker, set to the precision of the level
+///
+/// # Returns
+/// An image of `MINIMAP_SIZE` by `MINIMAP_SIZE` pixels
+pub fn render(request: &MinimapRequest, palette: &Palette, scratch: &mut Scratch) -> MinimapImage {
+    let scale = request.rect.scale();
+    scratch.set_prec(request.rect.precision());
+    let data = (0..MINIMAP_SIZE * MINIMAP_SIZE)
+        .map(|index| {
+            let escape = iterate::get_pixel_escape(
+                (index % MINIMAP_SIZE) as f64 + 0.5,
+                (index / MINIMAP_SIZE) as f64 + 0.5,
+                &request.rect,
+                &scale,
+                request.max_iter,
+                scratch,
+            );
+            let (r, g, b) = palette.color(escape);
+            [r, g, b, 0xFF]
+        })
+        .collect();
+    MinimapImage {
+        level: request.level,
+        id: request.id,
+        data,
+    }
+}
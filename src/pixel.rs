@@ -1,3 +1,5 @@
+use std::sync::LazyLock;
+
 /// A two-dimensional size with width and height as 32-bit unsigned integers.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Size32 {
@@ -24,24 +26,71 @@ pub enum ScaleDirection {
 /// Translates a pixel buffer by the specified delta
 ///
 /// # Arguments
-/// * `src_buffer` - Source buffer containing RGBA pixel data
+/// * `src_buffer` - Source buffer containing one element per pixel
 /// * `size` - Dimensions of the buffer in pixels
-/// * `pitch` - Number of bytes per row in the buffer
+/// * `pitch` - Number of pixels per row in the buffer
 /// * `delta` - Pixel offset to apply
 ///
 /// # Returns
-/// A new buffer containing the translated pixel data
-pub fn translate_rect(src_buffer: &[u8], size: Size32, pitch: u32, delta: Point32) -> Vec<u8> {
-    let mut dst_buffer = vec![0; (pitch * size.h) as usize];
+/// A new buffer containing the translated pixel data, with uncovered pixels set to default
+pub fn translate_rect<P: Copy + Default>(
+    src_buffer: &[P],
+    size: Size32,
+    pitch: u32,
+    delta: Point32,
+) -> Vec<P> {
+    let mut dst_buffer = vec![P::default(); (pitch * size.h) as usize];
     let width = (size.w.saturating_sub(delta.x.unsigned_abs())) as usize;
     let height = (size.h.saturating_sub(delta.y.unsigned_abs())) as usize;
-    let src_offset = (delta.y.max(0) * pitch as i32 + delta.x.max(0) * 4) as usize;
-    let dst_offset = ((-delta.y).max(0) * pitch as i32 + (-delta.x).max(0) * 4) as usize;
+    let src_offset = (delta.y.max(0) * pitch as i32 + delta.x.max(0)) as usize;
+    let dst_offset = ((-delta.y).max(0) * pitch as i32 + (-delta.x).max(0)) as usize;
 
     for y in (0..height * pitch as usize).step_by(pitch as usize) {
         let src = y + src_offset;
         let dst = y + dst_offset;
-        dst_buffer[dst..dst + width * 4].copy_from_slice(&src_buffer[src..src + width * 4]);
+        dst_buffer[dst..dst + width].copy_from_slice(&src_buffer[src..src + width]);
+    }
+
+    dst_buffer
+}
+
+/// Rotates a pixel buffer around a pixel
+///
+/// Each destination pixel takes the nearest source pixel at its own position turned by `angle`
+/// around `center`, so the content turns the opposite way.
+///
+/// # Arguments
+/// * `src_buffer` - Source buffer containing one element per pixel
+/// * `size` - Dimensions of the buffer in pixels
+/// * `pitch` - Number of pixels per row in the buffer
+/// * `center` - Pixel that stays in place
+/// * `angle` - Rotation in degrees, clockwise with rows running down
+///
+/// # Returns
+/// A new buffer containing the rotated pixel data, with uncovered pixels set to default
+pub fn rotate_rect<P: Copy + Default>(
+    src_buffer: &[P],
+    size: Size32,
+    pitch: u32,
+    center: Point32,
+    angle: f32,
+) -> Vec<P> {
+    let mut dst_buffer = vec![P::default(); (pitch * size.h) as usize];
+    let (sin, cos) = (angle as f64).to_radians().sin_cos();
+    let center_x = center.x as f64;
+    let center_y = center.y as f64;
+
+    for y in 0..size.h {
+        let dy = y as f64 - center_y;
+        for x in 0..size.w {
+            let dx = x as f64 - center_x;
+            let src_x = (center_x + cos * dx - sin * dy).round();
+            let src_y = (center_y + sin * dx + cos * dy).round();
+            if src_x >= 0.0 && src_y >= 0.0 && src_x < size.w as f64 && src_y < size.h as f64 {
+                dst_buffer[(y * pitch + x) as usize] =
+                    src_buffer[src_y as usize * pitch as usize + src_x as usize];
+            }
+        }
     }
 
     dst_buffer
@@ -50,29 +99,29 @@ pub fn translate_rect(src_buffer: &[u8], size: Size32, pitch: u32, delta: Point3
 /// Extends a pixel buffer to a new size
 ///
 /// # Arguments
-/// * `src_buffer` - Source buffer containing RGBA pixel data
+/// * `src_buffer` - Source buffer containing one element per pixel
 /// * `src_size` - Dimensions of the source buffer in pixels
-/// * `src_pitch` - Number of bytes per row in the source buffer
+/// * `src_pitch` - Number of pixels per row in the source buffer
 /// * `dst_size` - Dimensions of the target buffer in pixels
-/// * `dst_pitch` - Number of bytes per row in the target buffer
+/// * `dst_pitch` - Number of pixels per row in the target buffer
 ///
 /// # Returns
-/// A new buffer with the extended dimensions
-pub fn extend_buffer(
-    src_buffer: &[u8],
+/// A new buffer with the extended dimensions, with uncovered pixels set to default
+pub fn extend_buffer<P: Copy + Default>(
+    src_buffer: &[P],
     src_size: Size32,
     src_pitch: u32,
     dst_size: Size32,
     dst_pitch: u32,
-) -> Vec<u8> {
-    let mut dst_buffer = vec![0; (dst_pitch * dst_size.h) as usize];
+) -> Vec<P> {
+    let mut dst_buffer = vec![P::default(); (dst_pitch * dst_size.h) as usize];
     let width = dst_size.w.min(src_size.w) as usize;
     let height = dst_size.h.min(src_size.h) as usize;
 
     for y in 0..height {
         let src = y * src_pitch as usize;
         let dst = y * dst_pitch as usize;
-        dst_buffer[dst..dst + width * 4].copy_from_slice(&src_buffer[src..src + width * 4]);
+        dst_buffer[dst..dst + width].copy_from_slice(&src_buffer[src..src + width]);
     }
 
     dst_buffer
@@ -80,24 +129,27 @@ pub fn extend_buffer(
 
 /// Scales a pixel buffer up or down by a factor of 2, with pixel offset
 ///
+/// When scaling up, each source pixel lands on the even row and column of its 2x2 block in
+/// the destination; when scaling down, each destination pixel comes from an even source pixel.
+///
 /// # Arguments
-/// * `src_buffer` - Source buffer containing RGBA pixel data
+/// * `src_buffer` - Source buffer containing one element per pixel
 /// * `size` - Dimensions of the buffer in pixels
-/// * `pitch` - Number of bytes per row in the buffer
+/// * `pitch` - Number of pixels per row in the buffer
 /// * `delta` - Pixel offset to apply during scaling
 /// * `direction` - Whether to scale up (2x) or down (0.5x)
 ///
 /// # Returns
-/// A new buffer containing the scaled pixel data
-pub fn scale_rect(
-    src_buffer: &[u8],
+/// A new buffer containing the scaled pixel data, with uncovered pixels set to default
+pub fn scale_rect<P: Copy + Default>(
+    src_buffer: &[P],
     size: Size32,
     pitch: u32,
     delta: Point32,
     direction: ScaleDirection,
-) -> Vec<u8> {
-    let mut dst_buffer = vec![0; (pitch * size.h) as usize];
-    let pitch = pitch as usize / 4;
+) -> Vec<P> {
+    let mut dst_buffer = vec![P::default(); (pitch * size.h) as usize];
+    let pitch = pitch as usize;
 
     match direction {
         ScaleDirection::Up => copy_rows_up(
@@ -126,9 +178,9 @@ pub fn scale_rect(
 }
 
 /// Copy a range of pixel rows from the source buffer to the destination buffer, scaling them up by 2
-fn copy_rows_up(
-    src_buffer: &[u8],
-    dst_buffer: &mut [u8],
+fn copy_rows_up<P: Copy>(
+    src_buffer: &[P],
+    dst_buffer: &mut [P],
     src_x: usize,
     src_y: usize,
     width: usize,
@@ -137,44 +189,41 @@ fn copy_rows_up(
     dst_pitch: usize,
 ) {
     let src_offset = src_y * src_pitch + src_x;
+    // Each source row fills two destination rows, which also works for odd pitches
     for (src_lower, dst_lower) in (src_offset..height * src_pitch + src_offset)
         .step_by(src_pitch)
-        .zip((0..height * dst_pitch).step_by(dst_pitch))
+        .zip((0..2 * height * dst_pitch).step_by(2 * dst_pitch))
     {
         copy_row_up(src_buffer, dst_buffer, src_lower, dst_lower, width);
         copy_row_up(
             src_buffer,
             dst_buffer,
             src_lower,
-            dst_lower + dst_pitch / 2,
+            dst_lower + dst_pitch,
             width,
         );
     }
 }
 
 /// Copy a single row of pixels from the source buffer to the destination buffer, scaling them up by 2
-fn copy_row_up(
-    src_buffer: &[u8],
-    dst_buffer: &mut [u8],
+fn copy_row_up<P: Copy>(
+    src_buffer: &[P],
+    dst_buffer: &mut [P],
     src_lower: usize,
     dst_lower: usize,
     width: usize,
 ) {
-    for (src, dst) in (src_lower * 4..(src_lower + width) * 4)
-        .step_by(4)
-        .zip((dst_lower * 8..(dst_lower + width) * 8).step_by(8))
-    {
+    for (src, dst) in (src_lower..src_lower + width).zip((dst_lower..).step_by(2)) {
         // Copy the source pixel to two adjacent pixels in the destination
-        let slice = &src_buffer[src..src + 4];
-        dst_buffer[dst..dst + 4].copy_from_slice(slice);
-        dst_buffer[dst + 4..dst + 8].copy_from_slice(slice);
+        dst_buffer[dst] = src_buffer[src];
+        dst_buffer[dst + 1] = src_buffer[src];
     }
 }
 
 /// Copy a range of pixel rows from the source buffer to the destination buffer, scaling them down by 2
-fn copy_rows_down(
-    src_buffer: &[u8],
-    dst_buffer: &mut [u8],
+fn copy_rows_down<P: Copy>(
+    src_buffer: &[P],
+    dst_buffer: &mut [P],
     dst_x: usize,
     dst_y: usize,
     width: usize,
@@ -192,18 +241,18 @@ fn copy_rows_down(
 }
 
 /// Copy a single row of pixels from the source buffer to the destination buffer, scaling them down by 2
-fn copy_row_down(
-    src_buffer: &[u8],
-    dst_buffer: &mut [u8],
+fn copy_row_down<P: Copy>(
+    src_buffer: &[P],
+    dst_buffer: &mut [P],
     src_lower: usize,
     dst_lower: usize,
     width: usize,
 ) {
-    for (src, dst) in (src_lower * 8..(src_lower + width) * 8)
-        .step_by(8)
-        .zip((dst_lower * 4..(dst_lower + width) * 4).step_by(4))
+    for (src, dst) in (src_lower * 2..)
+        .step_by(2)
+        .zip(dst_lower..dst_lower + width)
     {
-        dst_buffer[dst..dst + 4].copy_from_slice(&src_buffer[src..src + 4]);
+        dst_buffer[dst] = src_buffer[src];
     }
 }
 
@@ -237,3 +286,258 @@ pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> (u8, u8, u8) {
         ((b + m) * 255.0) as u8,
     )
 }
+
+/// Measures how different two colors look
+///
+/// # Arguments
+/// * `a` - First RGBA color
+/// * `b` - Second RGBA color
+///
+/// # Returns
+/// The largest difference over the color channels (0-255)
+pub fn color_distance(a: [u8; 4], b: [u8; 4]) -> u32 {
+    a.iter()
+        .zip(b.iter())
+        .take(3)
+        .map(|(&a, &b)| a.abs_diff(b) as u32)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Offset of a subsample within a pixel, following the R2 low-discrepancy sequence
+///
+/// # Arguments
+/// * `index` - Index of the subsample, starting at 1
+///
+/// # Returns
+/// The offset from the pixel center, in pixels (-0.5 to 0.5)
+pub fn subsample_offset(index: u32) -> (f64, f64) {
+    // Inverses of the plastic number and its square
+    const ALPHA_X: f64 = 0.754_877_666_246_692_7;
+    const ALPHA_Y: f64 = 0.569_840_290_998_053_3;
+    (
+        (0.5 + index as f64 * ALPHA_X).fract() - 0.5,
+        (0.5 + index as f64 * ALPHA_Y).fract() - 0.5,
+    )
+}
+
+/// Reconstruction filter used when downsampling a supersampled buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// Average of the covered source pixels
+    Box,
+    /// Linear falloff (bilinear)
+    Tent,
+    /// Windowed sinc with three lobes, sharpest but may ring
+    Lanczos,
+    /// Mitchell-Netravali cubic (B = C = 1/3), a balance of sharpness and ringing
+    Mitchell,
+}
+
+impl Filter {
+    /// The next filter, cycling back to the first
+    pub fn next(self) -> Self {
+        match self {
+            Self::Box => Self::Tent,
+            Self::Tent => Self::Lanczos,
+            Self::Lanczos => Self::Mitchell,
+            Self::Mitchell => Self::Box,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Box => "box",
+            Self::Tent => "tent",
+            Self::Lanczos => "lanczos",
+            Self::Mitchell => "mitchell",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Box, Self::Tent, Self::Lanczos, Self::Mitchell]
+            .into_iter()
+            .find(|filter| filter.name() == name)
+    }
+
+    /// Support of the filter in destination pixels
+    fn radius(self) -> f32 {
+        match self {
+            Self::Box => 0.5,
+            Self::Tent => 1.0,
+            Self::Lanczos => 3.0,
+            Self::Mitchell => 2.0,
+        }
+    }
+
+    /// Filter weight at a distance in destination pixels
+    fn weight(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            Self::Box => (x <= 0.5) as u8 as f32,
+            Self::Tent => (1.0 - x).max(0.0),
+            Self::Lanczos => {
+                if x < 1e-6 {
+                    1.0
+                } else if x < 3.0 {
+                    let pi_x = std::f32::consts::PI * x;
+                    3.0 * pi_x.sin() * (pi_x / 3.0).sin() / (pi_x * pi_x)
+                } else {
+                    0.0
+                }
+            }
+            Self::Mitchell => {
+                const B: f32 = 1.0 / 3.0;
+                const C: f32 = 1.0 / 3.0;
+                let value = if x < 1.0 {
+                    (12.0 - 9.0 * B - 6.0 * C) * x * x * x
+                        + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+                        + (6.0 - 2.0 * B)
+                } else if x < 2.0 {
+                    (-B - 6.0 * C) * x * x * x
+                        + (6.0 * B + 30.0 * C) * x * x
+                        + (-12.0 * B - 48.0 * C) * x
+                        + (8.0 * B + 24.0 * C)
+                } else {
+                    0.0
+                };
+                value / 6.0
+            }
+        }
+    }
+}
+
+/// Lookup table from 8-bit sRGB to linear intensity
+static SRGB_TO_LINEAR: LazyLock<[f32; 256]> = LazyLock::new(|| {
+    std::array::from_fn(|value| {
+        let value = value as f32 / 255.0;
+        if value <= 0.04045 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    })
+});
+
+/// Size of the lookup table from linear intensity to 8-bit sRGB
+const LINEAR_STEPS: usize = 4096;
+
+/// Lookup table from quantized linear intensity to 8-bit sRGB
+static LINEAR_TO_SRGB: LazyLock<[u8; LINEAR_STEPS + 1]> = LazyLock::new(|| {
+    std::array::from_fn(|step| {
+        let value = step as f32 / LINEAR_STEPS as f32;
+        let encoded = if value <= 0.003_130_8 {
+            value * 12.92
+        } else {
+            1.055 * value.powf(1.0 / 2.4) - 0.055
+        };
+        (encoded * 255.0 + 0.5) as u8
+    })
+});
+
+/// Converts an 8-bit sRGB channel to linear intensity (0.0-1.0)
+pub fn srgb_to_linear(value: u8) -> f32 {
+    SRGB_TO_LINEAR[value as usize]
+}
+
+/// Converts a linear intensity to an 8-bit sRGB channel, clamping out-of-range values
+pub fn linear_to_srgb(value: f32) -> u8 {
+    LINEAR_TO_SRGB[(value.clamp(0.0, 1.0) * LINEAR_STEPS as f32 + 0.5) as usize]
+}
+
+/// Source pixel range and normalized weights contributing to each destination pixel
+fn filter_taps(dst_len: u32, src_len: u32, factor: u32, filter: Filter) -> Vec<(usize, Vec<f32>)> {
+    let factor = factor as f32;
+    let support = filter.radius() * factor;
+    (0..dst_len)
+        .map(|dst| {
+            let center = (dst as f32 + 0.5) * factor - 0.5;
+            let first = (center - support).ceil().max(0.0) as usize;
+            let last = ((center + support).floor() as usize).min(src_len as usize - 1);
+            let mut weights: Vec<f32> = (first..=last)
+                .map(|src| filter.weight((src as f32 - center) / factor))
+                .collect();
+            let total: f32 = weights.iter().sum();
+            if total.abs() > f32::EPSILON {
+                weights.iter_mut().for_each(|weight| *weight /= total);
+            }
+            (first, weights)
+        })
+        .collect()
+}
+
+/// Downsamples a supersampled buffer by an integer factor, filtering in linear light
+///
+/// # Arguments
+/// * `src_buffer` - Source buffer containing RGBA pixel data
+/// * `src_size` - Dimensions of the source buffer in pixels
+/// * `factor` - Number of source pixels per destination pixel along each axis
+/// * `filter` - Reconstruction filter to apply
+///
+/// # Returns
+/// A new buffer of the source dimensions divided by `factor`
+pub fn downsample(
+    src_buffer: &[[u8; 4]],
+    src_size: Size32,
+    factor: u32,
+    filter: Filter,
+) -> Vec<[u8; 4]> {
+    let mut dst_buffer = Vec::new();
+    downsample_into(src_buffer, src_size, factor, filter, &mut dst_buffer);
+    dst_buffer
+}
+
+/// Downsamples like `downsample`, reusing the allocation of `dst_buffer` for the result
+pub fn downsample_into(
+    src_buffer: &[[u8; 4]],
+    src_size: Size32,
+    factor: u32,
+    filter: Filter,
+    dst_buffer: &mut Vec<[u8; 4]>,
+) {
+    dst_buffer.clear();
+    if factor <= 1 {
+        dst_buffer.extend_from_slice(src_buffer);
+        return;
+    }
+    let dst_size = Size32 {
+        w: src_size.w / factor,
+        h: src_size.h / factor,
+    };
+    let src_width = src_size.w as usize;
+    let dst_width = dst_size.w as usize;
+
+    // Filter horizontally into a linear intermediate of destination width and source height
+    let taps = filter_taps(dst_size.w, src_size.w, factor, filter);
+    let mut linear = vec![[0.0_f32; 3]; dst_width * src_size.h as usize];
+    for (src_row, dst_row) in src_buffer
+        .chunks_exact(src_width)
+        .zip(linear.chunks_exact_mut(dst_width))
+    {
+        for ((first, weights), dst) in taps.iter().zip(dst_row.iter_mut()) {
+            for (src, weight) in src_row[*first..].iter().zip(weights) {
+                for channel in 0..3 {
+                    dst[channel] += srgb_to_linear(src[channel]) * weight;
+                }
+            }
+        }
+    }
+
+    // Filter vertically and encode back to sRGB
+    let taps = filter_taps(dst_size.h, src_size.h, factor, filter);
+    dst_buffer.resize(dst_width * dst_size.h as usize, [0, 0, 0, 0xFF]);
+    for ((first, weights), dst_row) in taps.iter().zip(dst_buffer.chunks_exact_mut(dst_width)) {
+        for (x, dst) in dst_row.iter_mut().enumerate() {
+            let mut sum = [0.0_f32; 3];
+            for (y, weight) in (*first..).zip(weights) {
+                let src = linear[y * dst_width + x];
+                for channel in 0..3 {
+                    sum[channel] += src[channel] * weight;
+                }
+            }
+            for channel in 0..3 {
+                dst[channel] = linear_to_srgb(sum[channel]);
+            }
+        }
+    }
+}
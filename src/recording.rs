@@ -0,0 +1,131 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+use crate::{
+    AppError,
+    location::{Location, parse_angle, parse_depth, parse_float},
+};
+
+/// View and iteration limit shown from `time` seconds into a recording
+pub struct Frame {
+    pub time: f32,
+    pub location: Location,
+    pub max_iter: u32,
+}
+
+/// Writes the views of a live exploration to a file as they change
+pub struct Recorder {
+    writer: BufWriter<File>,
+    start: Instant,
+    /// Last written view, so frames where nothing moved are skipped
+    last: String,
+}
+
+impl Recorder {
+    /// Starts a recording, replacing any file at `path`
+    pub fn create(path: &Path) -> Result<Self, AppError> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(
+            writer,
+            "# Fractal recording: seconds, real, imag, depth, angle and max_iter per line"
+        )?;
+        Ok(Self {
+            writer,
+            start: Instant::now(),
+            last: String::new(),
+        })
+    }
+
+    /// Appends the current view, unless it is the same as in the previous frame
+    pub fn record(&mut self, location: &Location, max_iter: u32) -> Result<(), AppError> {
+        let view = format!(
+            "{} {} {} {} {}",
+            location.real.to_string_radix(10, None),
+            location.imag.to_string_radix(10, None),
+            location.depth,
+            location.angle,
+            max_iter
+        );
+        if view == self.last {
+            return Ok(());
+        }
+        writeln!(
+            self.writer,
+            "{:.3} {}",
+            self.start.elapsed().as_secs_f32(),
+            view
+        )?;
+        self.last = view;
+        Ok(())
+    }
+
+    /// Writes out what is still buffered
+    pub fn finish(mut self) -> Result<(), AppError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads the frames of a recording
+pub fn load(path: &Path) -> Result<Vec<Frame>, AppError> {
+    let text = fs::read_to_string(path)?;
+    parse(&text).map_err(|msg| AppError::InvalidConfig(format!("{}: {msg}", path.display())))
+}
+
+fn parse(text: &str) -> Result<Vec<Frame>, String> {
+    let mut frames: Vec<Frame> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let frame = (|| {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            let [time, real, imag, depth, angle, max_iter] = values[..] else {
+                return Err(format!("expected 6 values, got {}", values.len()));
+            };
+            let time = time
+                .parse::<f32>()
+                .ok()
+                .filter(|time| time.is_finite())
+                .ok_or_else(|| format!("invalid time '{time}'"))?;
+            Ok(Frame {
+                time,
+                location: Location {
+                    real: parse_float(real)?,
+                    imag: parse_float(imag)?,
+                    depth: parse_depth(depth)?,
+                    angle: parse_angle(angle)?,
+                },
+                max_iter: max_iter
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid iterations '{max_iter}'"))?
+                    .max(1),
+            })
+        })()
+        .map_err(|msg| format!("line {}: {msg}", number + 1))?;
+        if frames.last().is_some_and(|last| last.time > frame.time) {
+            return Err(format!("line {}: time goes backwards", number + 1));
+        }
+        frames.push(frame);
+    }
+    if frames.is_empty() {
+        return Err("no frames".to_string());
+    }
+    Ok(frames)
+}
+
+/// Index of the frame showing at a time, which is the last one that started before it
+///
+/// # Arguments
+/// * `frames` - Frames in order of time
+/// * `time` - Seconds since the start of the recording
+pub fn frame_at(frames: &[Frame], time: f32) -> usize {
+    frames
+        .partition_point(|frame| frame.time <= time)
+        .saturating_sub(1)
+}
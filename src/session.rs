@@ -0,0 +1,107 @@
+use std::{fs, io::ErrorKind, path::PathBuf};
+
+use crate::{
+    AppError, Config, config,
+    location::{Location, parse_angle, parse_depth, parse_float},
+};
+
+/// Location of the session file in the user's state directory
+fn path() -> Option<PathBuf> {
+    Some(config::user_dir("XDG_STATE_HOME", ".local/state")?.join("session"))
+}
+
+/// Reads the last session, applying its settings to `config`
+///
+/// # Arguments
+/// * `config` - Configuration to update with the window size, coloring and iteration settings,
+///   before the config file and command-line arguments are applied over them
+///
+/// # Returns
+/// The location of the last session, or `None` when there is none
+pub fn load(config: &mut Config) -> Result<Option<Location>, AppError> {
+    let Some(path) = path() else {
+        return Ok(None);
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    parse(&text, config)
+        .map(Some)
+        .map_err(|msg| AppError::InvalidConfig(format!("{}: {msg}", path.display())))
+}
+
+/// Writes the session, replacing the previous one
+pub fn save(text: &str) -> Result<(), AppError> {
+    let Some(path) = path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write next to the session and rename, so a crash never leaves a partial file
+    let temp = path.with_extension("tmp");
+    fs::write(&temp, text)?;
+    fs::rename(&temp, &path)?;
+    Ok(())
+}
+
+/// Serializes the view and the settings that can change while exploring
+///
+/// Coordinates are written with all their digits, so deep zooms are restored exactly.
+pub fn format(config: &Config, location: &Location, max_iter: u32, auto_iter: bool) -> String {
+    format!(
+        "# Fractal session, restored on launch unless started with --fresh\n\
+         real = {}\n\
+         imag = {}\n\
+         depth = {}\n\
+         angle = {}\n\
+         max_iter = {}\n\
+         auto_iter = {}\n\
+         window_size = {}x{}\n\
+         downsample_filter = {}\n\
+         color_cycle = {}\n\
+         saturation = {}\n\
+         interior_value = {}\n",
+        location.real.to_string_radix(10, None),
+        location.imag.to_string_radix(10, None),
+        location.depth,
+        location.angle,
+        max_iter,
+        auto_iter,
+        config.window_size.w,
+        config.window_size.h,
+        config.downsample_filter.name(),
+        config.color_cycle,
+        config.saturation,
+        config.interior_value,
+    )
+}
+
+/// Parses a session, only touching `config` when all of it is valid
+fn parse(text: &str, config: &mut Config) -> Result<Location, String> {
+    let mut settings = *config;
+    let mut location = Location::default();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+        let result = match key {
+            "real" => parse_float(value).map(|real| location.real = real),
+            "imag" => parse_float(value).map(|imag| location.imag = imag),
+            "depth" => parse_depth(value).map(|depth| location.depth = depth),
+            "angle" => parse_angle(value).map(|angle| location.angle = angle),
+            _ => config::set(&mut settings, key, value),
+        };
+        result.map_err(|msg| format!("line {}: {msg}", number + 1))?;
+    }
+    config::validate(&settings)?;
+    *config = settings;
+    Ok(location)
+}
@@ -0,0 +1,117 @@
+use crate::{
+    Escape,
+    iterate::{Brent, BrentStep, ESCAPE_RADIUS_SQ},
+};
+
+/// Number of points iterated in lock-step
+pub const LANES: usize = 4;
+
+/// Iterates a batch of points in lock-step lanes, refilling a lane as soon as its point finishes
+///
+/// The arithmetic runs on fixed-size arrays, which the compiler turns into vector instructions.
+/// Finished lanes are masked out until they are refilled with the next pending point. Each lane
+/// takes the same steps as `iterate::escape`, with its own cycle detection.
+///
+/// # Arguments
+/// * `points` - Real and imaginary parts of the points to iterate
+/// * `max_iter` - Maximum number of iterations per point
+/// * `epsilon` - Distance below which an orbit is considered periodic
+///
+/// # Returns
+/// The escape result of each point, in the order of `points`
+pub fn escape_batch(points: &[(f64, f64)], max_iter: u32, epsilon: f64) -> Vec<Escape> {
+    let mut results = vec![Escape::Limit; points.len()];
+    let mut next = 0;
+
+    let mut index = [usize::MAX; LANES];
+    let mut c_real = [0.0; LANES];
+    let mut c_imag = [0.0; LANES];
+    let mut z_real = [0.0; LANES];
+    let mut z_imag = [0.0; LANES];
+    let mut iter = [0u32; LANES];
+
+    let mut check_real = [0.0; LANES];
+    let mut check_imag = [0.0; LANES];
+    let mut brent = [Brent::new(); LANES];
+
+    let mut active = 0;
+    loop {
+        // Refill idle lanes with pending points
+        for lane in 0..LANES {
+            if index[lane] != usize::MAX || next >= points.len() {
+                continue;
+            }
+            index[lane] = next;
+            (c_real[lane], c_imag[lane]) = points[next];
+            z_real[lane] = 0.0;
+            z_imag[lane] = 0.0;
+            iter[lane] = 0;
+            check_real[lane] = 0.0;
+            check_imag[lane] = 0.0;
+            brent[lane] = Brent::new();
+            next += 1;
+            active += 1;
+        }
+        if active == 0 {
+            break;
+        }
+
+        let mut real_sq = [0.0; LANES];
+        let mut imag_sq = [0.0; LANES];
+        let mut mag_sq = [0.0; LANES];
+        for lane in 0..LANES {
+            real_sq[lane] = z_real[lane] * z_real[lane];
+            imag_sq[lane] = z_imag[lane] * z_imag[lane];
+            mag_sq[lane] = real_sq[lane] + imag_sq[lane];
+        }
+
+        // Check if points escape or run out of iterations
+        for lane in 0..LANES {
+            if index[lane] == usize::MAX {
+                continue;
+            }
+            if iter[lane] >= max_iter {
+                results[index[lane]] = Escape::Limit;
+            } else if mag_sq[lane] > ESCAPE_RADIUS_SQ {
+                results[index[lane]] = Escape::Escaped {
+                    iter: iter[lane],
+                    mag_sq: mag_sq[lane] as f32,
+                };
+            } else {
+                continue;
+            }
+            index[lane] = usize::MAX;
+            active -= 1;
+        }
+
+        // Apply the Mandelbrot iteration: z = z² + c
+        for lane in 0..LANES {
+            z_imag[lane] = 2.0 * z_real[lane] * z_imag[lane] + c_imag[lane];
+            z_real[lane] = real_sq[lane] - imag_sq[lane] + c_real[lane];
+            iter[lane] += 1;
+        }
+
+        // Check if orbits returned to their snapshot
+        for lane in 0..LANES {
+            if index[lane] == usize::MAX {
+                continue;
+            }
+            let returned = (z_real[lane] - check_real[lane]).abs() < epsilon
+                && (z_imag[lane] - check_imag[lane]).abs() < epsilon;
+            match brent[lane].step(returned) {
+                BrentStep::Period(period) => {
+                    results[index[lane]] = Escape::Interior { period };
+                    index[lane] = usize::MAX;
+                    active -= 1;
+                }
+                BrentStep::Snapshot => {
+                    check_real[lane] = z_real[lane];
+                    check_imag[lane] = z_imag[lane];
+                }
+                BrentStep::Continue => {}
+            }
+        }
+    }
+
+    results
+}
@@ -0,0 +1,140 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use crate::pixel::{Point32, Size32};
+
+/// Width and height of a full tile in pixels, a multiple of the coarsest sample spacing
+pub const TILE_SIZE: u32 = 64;
+
+/// A rectangular region of the render buffer that is computed as one unit of work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+impl Tile {
+    /// Grows the tile by `border` pixels on each side, clipped to the buffer
+    pub fn expand(&self, border: u32, size: Size32) -> Tile {
+        let x = self.x.saturating_sub(border);
+        let y = self.y.saturating_sub(border);
+        Tile {
+            x,
+            y,
+            w: (self.x + self.w + border).min(size.w) - x,
+            h: (self.y + self.h + border).min(size.h) - y,
+        }
+    }
+
+    /// Copies the pixels covered by the tile out of a buffer
+    ///
+    /// # Arguments
+    /// * `src_buffer` - Source buffer containing one element per pixel
+    /// * `pitch` - Number of pixels per row in the source buffer
+    ///
+    /// # Returns
+    /// A new buffer of the tile's dimensions
+    pub fn copy_from<P: Copy>(&self, src_buffer: &[P], pitch: u32) -> Vec<P> {
+        (self.y..self.y + self.h)
+            .flat_map(|y| {
+                let index = (y * pitch + self.x) as usize;
+                src_buffer[index..index + self.w as usize].iter().copied()
+            })
+            .collect()
+    }
+
+    /// Squared distance from the tile center to a point
+    fn distance_sq(&self, point: Point32) -> u64 {
+        let dx = (self.x + self.w / 2) as i64 - point.x as i64;
+        let dy = (self.y + self.h / 2) as i64 - point.y as i64;
+        (dx * dx + dy * dy) as u64
+    }
+}
+
+/// A tile to be computed at a given sample spacing, for a given view generation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Job {
+    pub tile: Tile,
+    pub step: u32,
+    pub generation: u64,
+    distance: u64,
+}
+
+impl Ord for Job {
+    /// Coarser passes come first, then tiles closer to the focus point
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.step
+            .cmp(&other.step)
+            .then_with(|| other.distance.cmp(&self.distance))
+    }
+}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Priority queue of tiles waiting to be computed
+pub struct TileQueue {
+    jobs: BinaryHeap<Job>,
+    focus: Point32,
+}
+
+impl TileQueue {
+    pub fn new() -> Self {
+        Self {
+            jobs: BinaryHeap::new(),
+            focus: Point32 { x: 0, y: 0 },
+        }
+    }
+
+    /// Replaces all queued jobs with every tile of the buffer
+    ///
+    /// # Arguments
+    /// * `size` - Dimensions of the buffer in pixels
+    /// * `step` - Sample spacing of the first pass
+    /// * `generation` - View generation the jobs belong to
+    /// * `focus` - Point in the buffer around which tiles are computed first
+    ///
+    /// # Returns
+    /// The number of tiles queued
+    pub fn reset(&mut self, size: Size32, step: u32, generation: u64, focus: Point32) -> u32 {
+        self.jobs.clear();
+        self.focus = focus;
+        let mut count = 0;
+        for y in (0..size.h).step_by(TILE_SIZE as usize) {
+            for x in (0..size.w).step_by(TILE_SIZE as usize) {
+                let tile = Tile {
+                    x,
+                    y,
+                    w: TILE_SIZE.min(size.w - x),
+                    h: TILE_SIZE.min(size.h - y),
+                };
+                self.push(tile, step, generation);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Queues a single tile
+    pub fn push(&mut self, tile: Tile, step: u32, generation: u64) {
+        self.jobs.push(Job {
+            tile,
+            step,
+            generation,
+            distance: tile.distance_sq(self.focus),
+        });
+    }
+
+    /// Takes the job with the highest priority
+    pub fn pop(&mut self) -> Option<Job> {
+        self.jobs.pop()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}